@@ -0,0 +1,6 @@
+CREATE TABLE ledger_entries (
+    division_id TEXT NOT NULL,
+    email TEXT NOT NULL,
+    serialized TEXT NOT NULL,
+    PRIMARY KEY (division_id, email)
+)
\ No newline at end of file
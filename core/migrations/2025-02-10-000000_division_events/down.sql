@@ -0,0 +1 @@
+DROP TABLE division_events
\ No newline at end of file
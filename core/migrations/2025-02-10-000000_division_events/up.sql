@@ -0,0 +1,7 @@
+CREATE TABLE division_events (
+    id BIGSERIAL PRIMARY KEY,
+    division_id TEXT NOT NULL,
+    serialized TEXT NOT NULL
+);
+
+CREATE INDEX division_events_division_id ON division_events (division_id)
\ No newline at end of file
@@ -99,16 +99,24 @@ impl PersistentDivision {
         id: String,
         basis: &BlockDivisionBasis,
     ) -> Result<PersistentDivision, Box<dyn std::error::Error>> {
-        let new_state = BlockDivisionState::new(basis);
+        basis.check()?;
+        let new_state = BlockDivisionState::new(basis)?;
+        PersistentDivision::insert(conn, id, &new_state)
+    }
+
+    pub fn insert(
+        conn: &mut PgConnection,
+        id: String,
+        state: &BlockDivisionState,
+    ) -> Result<PersistentDivision, Box<dyn std::error::Error>> {
         let insertion = PersistentDivision {
             id: id,
-            serialized: serde_json::to_string(&new_state)?,
+            serialized: serde_json::to_string(state)?,
         };
 
         diesel::insert_into(divisions::table)
             .values(&insertion)
-            .execute(conn)
-            .expect("Error creating new persistent division");
+            .execute(conn)?;
 
         Ok(insertion)
     }
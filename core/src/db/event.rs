@@ -0,0 +1,63 @@
+use diesel::prelude::*;
+
+use crate::{division::history::EventRecord, schema::division_events};
+
+#[derive(Queryable, Selectable, Debug, PartialEq, Clone)]
+#[diesel(table_name = crate::schema::division_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PersistentEvent {
+    id: i64, //Insertion order, which replay follows
+    division_id: String,
+    serialized: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::division_events)]
+struct NewPersistentEvent {
+    division_id: String,
+    serialized: String,
+}
+
+impl PersistentEvent {
+    pub fn append(
+        conn: &mut PgConnection,
+        division_id: &str,
+        record: &EventRecord,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        diesel::insert_into(division_events::table)
+            .values(NewPersistentEvent {
+                division_id: division_id.to_string(),
+                serialized: serde_json::to_string(record)?,
+            })
+            .execute(conn)?;
+        Ok(())
+    }
+
+    //Oldest first
+    pub fn get(
+        conn: &mut PgConnection,
+        division_id: &str,
+    ) -> Result<Vec<EventRecord>, Box<dyn std::error::Error>> {
+        let persistent = division_events::table
+            .filter(division_events::division_id.eq(division_id.to_string()))
+            .order(division_events::id)
+            .select(PersistentEvent::as_select())
+            .load(conn)?;
+
+        let mut retval: Vec<EventRecord> = Vec::new();
+        for event in persistent {
+            retval.push(serde_json::from_str(&event.serialized)?);
+        }
+        Ok(retval)
+    }
+
+    pub fn exists(
+        conn: &mut PgConnection,
+        division_id: &str,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        Ok(diesel::select(diesel::dsl::exists(
+            division_events::table.filter(division_events::division_id.eq(division_id.to_string())),
+        ))
+        .get_result(conn)?)
+    }
+}
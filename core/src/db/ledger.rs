@@ -0,0 +1,73 @@
+use diesel::prelude::*;
+
+use crate::{
+    division::ledger::{ledger_email, Ledger, LedgerEntry},
+    schema::ledger_entries,
+};
+
+define_sql_function!(fn lower(x: diesel::sql_types::Text) -> diesel::sql_types::Text);
+
+#[derive(Queryable, Selectable, Insertable, Debug, PartialEq, Clone)]
+#[diesel(table_name = crate::schema::ledger_entries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PersistentLedgerEntry {
+    division_id: String,
+    email: String,
+    serialized: String,
+}
+
+impl PersistentLedgerEntry {
+    //Entries for every email, or only the given one
+    pub fn get(
+        conn: &mut PgConnection,
+        email: Option<&str>,
+    ) -> Result<Ledger, Box<dyn std::error::Error>> {
+        let mut query = ledger_entries::table
+            .select(PersistentLedgerEntry::as_select())
+            .order((ledger_entries::email, ledger_entries::division_id))
+            .into_boxed();
+        if let Some(email) = email {
+            query = query.filter(lower(ledger_entries::email).eq(ledger_email(email)));
+        }
+
+        let mut retval = Ledger::new();
+        for persistent in query.load(conn)? {
+            retval
+                .entry(ledger_email(&persistent.email))
+                .or_default()
+                .push(serde_json::from_str(&persistent.serialized)?);
+        }
+        Ok(retval)
+    }
+
+    //Replaces the division's entries in one transaction, so recording a division twice doesn't double count it
+    pub fn replace_division(
+        conn: &mut PgConnection,
+        division_id: &str,
+        entries: &Vec<LedgerEntry>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut insertions: Vec<PersistentLedgerEntry> = Vec::new();
+        for entry in entries {
+            insertions.push(PersistentLedgerEntry {
+                division_id: division_id.to_string(),
+                email: ledger_email(&entry.email),
+                serialized: serde_json::to_string(entry)?,
+            });
+        }
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(
+                ledger_entries::table
+                    .filter(ledger_entries::division_id.eq(division_id.to_string())),
+            )
+            .execute(conn)?;
+            diesel::insert_into(ledger_entries::table)
+                .values(&insertions)
+                .on_conflict_do_nothing() //Two participants sharing an email only count once
+                .execute(conn)?;
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,342 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use diesel::r2d2::ManageConnection;
+
+use crate::division::{
+    history::EventRecord,
+    ledger::{ledger_email, Ledger, LedgerEntry},
+    state::BlockDivisionState,
+};
+
+use super::store::DivisionStore;
+
+#[derive(Default, Clone)]
+struct InMemoryTables {
+    divisions: BTreeMap<String, String>, //Serialized like the divisions table so round trips are exercised
+    key_val_store: BTreeMap<String, String>,
+    ledger: BTreeMap<(String, String), String>, //Keyed by division id and email, like the ledger_entries table
+    events: Vec<(String, String)>, //Division id and serialized event in insertion order, like the division_events table
+}
+
+//Clones share the same tables, so this also works as its own r2d2 connection manager.
+#[derive(Clone, Default)]
+pub struct InMemoryDivisionStore {
+    tables: Arc<Mutex<InMemoryTables>>,
+}
+
+impl InMemoryDivisionStore {
+    pub fn new() -> InMemoryDivisionStore {
+        InMemoryDivisionStore::default()
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, InMemoryTables>, Box<dyn std::error::Error>> {
+        match self.tables.lock() {
+            Ok(tables) => Ok(tables),
+            Err(_) => Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "In-memory store is poisoned.",
+            ))),
+        }
+    }
+}
+
+impl DivisionStore for InMemoryDivisionStore {
+    fn get_state(
+        &mut self,
+        id: &str,
+    ) -> Result<Option<BlockDivisionState>, Box<dyn std::error::Error>> {
+        match self.lock()?.divisions.get(id) {
+            Some(serialized) => Ok(Some(serde_json::from_str(serialized)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn get_all_states(
+        &mut self,
+    ) -> Result<BTreeMap<String, BlockDivisionState>, Box<dyn std::error::Error>> {
+        let mut retval: BTreeMap<String, BlockDivisionState> = BTreeMap::new();
+        for (id, serialized) in self.lock()?.divisions.iter() {
+            retval.insert(id.to_string(), serde_json::from_str(serialized)?);
+        }
+        Ok(retval)
+    }
+
+    fn insert_state(
+        &mut self,
+        id: &str,
+        state: &BlockDivisionState,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = serde_json::to_string(state)?;
+        match self.lock()?.divisions.entry(id.to_string()) {
+            std::collections::btree_map::Entry::Vacant(entry) => {
+                entry.insert(serialized);
+                Ok(())
+            }
+            std::collections::btree_map::Entry::Occupied(_) => Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("State with id {} already exists.", id),
+            ))),
+        }
+    }
+
+    fn update_state(
+        &mut self,
+        id: &str,
+        state: &BlockDivisionState,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = serde_json::to_string(state)?;
+        //Like the diesel update, updating a missing id changes nothing.
+        if let Some(existing) = self.lock()?.divisions.get_mut(id) {
+            *existing = serialized;
+        }
+        Ok(())
+    }
+
+    fn delete_state(&mut self, id: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        match self.lock()?.divisions.remove(id) {
+            Some(_) => Ok(1),
+            None => Ok(0),
+        }
+    }
+
+    fn get_value(&mut self, key: &str) -> Option<String> {
+        self.lock()
+            .expect("Should always return.")
+            .key_val_store
+            .get(key)
+            .cloned()
+    }
+
+    fn set_value(
+        &mut self,
+        key: &str,
+        new_value: Option<String>,
+        allow_overwrite: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut tables = self.lock()?;
+        match new_value {
+            Some(new_value) => match tables.key_val_store.get(key) {
+                Some(current_value) if !allow_overwrite => match *current_value == new_value {
+                    true => {
+                        println!(
+                            "Key value pair already exists, but current value matches new value."
+                        );
+                        Ok(())
+                    }
+                    false => Err(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "Key value pair already exists. Current value: {}, New value: {}",
+                            current_value, new_value
+                        ),
+                    ))),
+                },
+                _ => {
+                    tables.key_val_store.insert(key.to_string(), new_value);
+                    Ok(())
+                }
+            },
+            None => match allow_overwrite {
+                true => {
+                    tables.key_val_store.remove(key);
+                    Ok(())
+                }
+                false => Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    "Deletion requested without write access.",
+                ))),
+            },
+        }
+    }
+
+    fn get_ledger(&mut self, email: Option<&str>) -> Result<Ledger, Box<dyn std::error::Error>> {
+        let mut retval = Ledger::new();
+        let email = email.map(ledger_email);
+        for ((_, entry_email), serialized) in self.lock()?.ledger.iter() {
+            let entry_email = ledger_email(entry_email);
+            if email.as_ref().map_or(true, |email| *email == entry_email) {
+                retval
+                    .entry(entry_email)
+                    .or_default()
+                    .push(serde_json::from_str(serialized)?);
+            }
+        }
+        Ok(retval)
+    }
+
+    fn record_ledger(
+        &mut self,
+        division_id: &str,
+        entries: &Vec<LedgerEntry>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut serialized: Vec<((String, String), String)> = Vec::new();
+        for entry in entries {
+            serialized.push((
+                (division_id.to_string(), ledger_email(&entry.email)),
+                serde_json::to_string(entry)?,
+            ));
+        }
+
+        let mut tables = self.lock()?;
+        tables.ledger.retain(|(id, _), _| id != division_id);
+        for (key, entry) in serialized {
+            tables.ledger.entry(key).or_insert(entry); //Two participants sharing an email only count once
+        }
+        Ok(())
+    }
+
+    fn append_event(
+        &mut self,
+        division_id: &str,
+        record: &EventRecord,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = serde_json::to_string(record)?;
+        self.lock()?
+            .events
+            .push((division_id.to_string(), serialized));
+        Ok(())
+    }
+
+    fn get_events(
+        &mut self,
+        division_id: &str,
+    ) -> Result<Vec<EventRecord>, Box<dyn std::error::Error>> {
+        let mut retval: Vec<EventRecord> = Vec::new();
+        for (id, serialized) in self.lock()?.events.iter() {
+            if id == division_id {
+                retval.push(serde_json::from_str(serialized)?);
+            }
+        }
+        Ok(retval)
+    }
+
+    fn has_events(&mut self, division_id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        Ok(self.lock()?.events.iter().any(|(id, _)| id == division_id))
+    }
+
+    //Puts the tables back as they were if any write fails
+    fn in_transaction(
+        &mut self,
+        writes: &mut dyn FnMut(&mut dyn DivisionStore) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let before = self.lock()?.clone();
+        let result = writes(self);
+        if result.is_err() {
+            *self.lock()? = before;
+        }
+        result
+    }
+}
+
+impl ManageConnection for InMemoryDivisionStore {
+    type Connection = InMemoryDivisionStore;
+    type Error = std::io::Error;
+
+    fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        Ok(self.clone())
+    }
+
+    fn is_valid(&self, _conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn has_broken(&self, _conn: &mut Self::Connection) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_delete() {
+        let store = &mut InMemoryDivisionStore::new();
+
+        let key = "test_some";
+        let value = "test some value";
+
+        store
+            .set_value(key, None, true)
+            .expect("Should be able to set.");
+        assert!(store.get_value(key).is_none());
+
+        store
+            .set_value(key, Some(value.to_string()), false)
+            .expect("Should be able to set.");
+        assert!(Some(value.to_string()) == store.get_value(key));
+
+        store
+            .set_value(key, Some(value.to_string()), false)
+            .expect("Setting the same value again should be fine.");
+
+        let should_fail = store.set_value(key, Some("Shouldn't work!".to_string()), false);
+        assert!(should_fail.is_err());
+
+        let should_fail = store.set_value(key, None, false);
+        assert!(should_fail.is_err());
+
+        store
+            .set_value(key, Some("Should work!".to_string()), true)
+            .expect("Should be able to set.");
+        assert!(Some("Should work!".to_string()) == store.get_value(key));
+
+        store
+            .set_value(key, None, true)
+            .expect("Should be able to set.");
+        assert!(store.get_value(key).is_none());
+    }
+
+    #[test]
+    fn clones_share_tables() {
+        let store = InMemoryDivisionStore::new();
+        let mut conn = store.connect().expect("Should connect.");
+
+        conn.set_value("shared", Some("value".to_string()), false)
+            .expect("Should be able to set.");
+        assert!(store.clone().get_value("shared") == Some("value".to_string()));
+    }
+
+    #[test]
+    fn failed_transaction_keeps_nothing() {
+        let store = &mut InMemoryDivisionStore::new();
+
+        let result = store.in_transaction(&mut |store| {
+            store.set_value("written", Some("value".to_string()), false)?;
+            store.set_value("written", Some("conflict".to_string()), false)
+        });
+        assert!(result.is_err());
+        assert!(store.get_value("written").is_none());
+
+        store
+            .in_transaction(&mut |store| {
+                store.set_value("written", Some("value".to_string()), false)
+            })
+            .expect("Should commit.");
+        assert!(store.get_value("written") == Some("value".to_string()));
+    }
+
+    #[test]
+    fn ledger_emails_ignore_case() {
+        let store = &mut InMemoryDivisionStore::new();
+        let entry = LedgerEntry {
+            division_id: "Last year".to_string(),
+            email: "A@Example.com".to_string(),
+            name: "A".to_string(),
+            wins: 1,
+            losses: 2,
+            holiday_buckets: 0,
+        };
+        store
+            .record_ledger("Last year", &Vec::from([entry]))
+            .expect("Should record.");
+
+        let ledger = store
+            .get_ledger(Some("a@example.com"))
+            .expect("Should work.");
+        assert!(ledger["a@example.com"].len() == 1);
+    }
+}
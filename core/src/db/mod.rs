@@ -3,7 +3,11 @@ use dotenvy::dotenv;
 use std::env;
 
 pub mod division;
+pub mod event;
 pub mod key_value;
+pub mod ledger;
+pub mod memory;
+pub mod store;
 pub mod user;
 
 pub fn database_url() -> String {
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use diesel::{Connection, PgConnection};
+
+use chrono::Utc;
+
+use crate::division::{
+    basis::BlockDivisionBasis,
+    history::{DivisionEvent, EventRecord},
+    ledger::{Ledger, LedgerEntry},
+    state::BlockDivisionState,
+};
+
+use super::{
+    division::PersistentDivision, event::PersistentEvent, key_value::KeyValuePair,
+    ledger::PersistentLedgerEntry,
+};
+
+//Everything the division engine and the server need to persist. PgConnection is the production implementation, InMemoryDivisionStore runs without a database.
+pub trait DivisionStore {
+    fn get_state(
+        &mut self,
+        id: &str,
+    ) -> Result<Option<BlockDivisionState>, Box<dyn std::error::Error>>;
+
+    fn get_all_states(
+        &mut self,
+    ) -> Result<BTreeMap<String, BlockDivisionState>, Box<dyn std::error::Error>>;
+
+    fn insert_state(
+        &mut self,
+        id: &str,
+        state: &BlockDivisionState,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    fn update_state(
+        &mut self,
+        id: &str,
+        state: &BlockDivisionState,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    fn delete_state(&mut self, id: &str) -> Result<usize, Box<dyn std::error::Error>>;
+
+    fn get_value(&mut self, key: &str) -> Option<String>;
+
+    fn set_value(
+        &mut self,
+        key: &str,
+        new_value: Option<String>,
+        allow_overwrite: bool,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    //Entries for every email, or only the given one
+    fn get_ledger(&mut self, email: Option<&str>) -> Result<Ledger, Box<dyn std::error::Error>>;
+
+    //Replaces any entries already recorded for the division
+    fn record_ledger(
+        &mut self,
+        division_id: &str,
+        entries: &Vec<LedgerEntry>,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    //Events are only ever appended, never changed or removed, even when the division is deleted
+    fn append_event(
+        &mut self,
+        division_id: &str,
+        record: &EventRecord,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    //Oldest first
+    fn get_events(
+        &mut self,
+        division_id: &str,
+    ) -> Result<Vec<EventRecord>, Box<dyn std::error::Error>>;
+
+    fn has_events(&mut self, division_id: &str) -> Result<bool, Box<dyn std::error::Error>>;
+
+    //Either every write is kept or none are, so a state is never saved without the event that explains it
+    fn in_transaction(
+        &mut self,
+        writes: &mut dyn FnMut(&mut dyn DivisionStore) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    fn delete_division(&mut self, id: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut deleted = 0;
+        self.in_transaction(&mut |store| {
+            deleted = store.delete_state(id)?;
+            if deleted > 0 {
+                store.append_event(id, &EventRecord::create(Utc::now(), DivisionEvent::Deleted))?;
+            }
+            Ok(())
+        })?;
+        Ok(deleted)
+    }
+
+    fn new_division(
+        &mut self,
+        id: &str,
+        basis: &BlockDivisionBasis,
+    ) -> Result<BlockDivisionState, Box<dyn std::error::Error>> {
+        basis.check()?;
+        let ledger = match basis.is_ledger_weighted() {
+            true => self.get_ledger(None)?,
+            false => Ledger::new(),
+        };
+        let state = BlockDivisionState::new_with_ledger(basis, &ledger)?;
+        self.in_transaction(&mut |store| {
+            store.insert_state(id, &state)?;
+            if let Some(lottery) = state.get_lottery() {
+                let created = DivisionEvent::Created {
+                    basis: basis.clone(),
+                    lottery: lottery.clone(),
+                };
+                store.append_event(id, &EventRecord::create(Utc::now(), created))?;
+            }
+            Ok(())
+        })?;
+        Ok(state)
+    }
+}
+
+impl DivisionStore for PgConnection {
+    fn get_state(
+        &mut self,
+        id: &str,
+    ) -> Result<Option<BlockDivisionState>, Box<dyn std::error::Error>> {
+        PersistentDivision::get_state_from_id(self, id)
+    }
+
+    fn get_all_states(
+        &mut self,
+    ) -> Result<BTreeMap<String, BlockDivisionState>, Box<dyn std::error::Error>> {
+        PersistentDivision::get_all(self)
+    }
+
+    fn insert_state(
+        &mut self,
+        id: &str,
+        state: &BlockDivisionState,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        PersistentDivision::insert(self, id.to_string(), state)?;
+        Ok(())
+    }
+
+    fn update_state(
+        &mut self,
+        id: &str,
+        state: &BlockDivisionState,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        PersistentDivision::update(self, id.to_string(), state)
+    }
+
+    fn delete_state(&mut self, id: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        Ok(PersistentDivision::delete_division(self, id.to_string())?)
+    }
+
+    fn get_value(&mut self, key: &str) -> Option<String> {
+        KeyValuePair::get(self, key)
+    }
+
+    fn set_value(
+        &mut self,
+        key: &str,
+        new_value: Option<String>,
+        allow_overwrite: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        KeyValuePair::set(self, key, new_value, allow_overwrite)
+    }
+
+    fn get_ledger(&mut self, email: Option<&str>) -> Result<Ledger, Box<dyn std::error::Error>> {
+        PersistentLedgerEntry::get(self, email)
+    }
+
+    fn record_ledger(
+        &mut self,
+        division_id: &str,
+        entries: &Vec<LedgerEntry>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        PersistentLedgerEntry::replace_division(self, division_id, entries)
+    }
+
+    fn append_event(
+        &mut self,
+        division_id: &str,
+        record: &EventRecord,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        PersistentEvent::append(self, division_id, record)
+    }
+
+    fn get_events(
+        &mut self,
+        division_id: &str,
+    ) -> Result<Vec<EventRecord>, Box<dyn std::error::Error>> {
+        PersistentEvent::get(self, division_id)
+    }
+
+    fn has_events(&mut self, division_id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        PersistentEvent::exists(self, division_id)
+    }
+
+    fn in_transaction(
+        &mut self,
+        writes: &mut dyn FnMut(&mut dyn DivisionStore) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Connection::transaction(self, |conn| writes(conn))
+    }
+}
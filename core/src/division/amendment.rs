@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    bucket::{BucketDef, BucketIndex, Ranks},
+    lottery::LotteryRecord,
+    participant::{ParticipantDef, ParticipantIndex},
+    round::{RoundIndex, RoundSchedule},
+    strategy::Allocation,
+};
+
+//A change to the basis of a division that has already started. Removed participants and buckets keep their index so links and history stay valid.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub enum BasisAmendment {
+    AddParticipant(ParticipantDef),
+    RemoveParticipant(ParticipantIndex),
+    UpdateParticipant {
+        participant: ParticipantIndex,
+        name: String,
+        email: String,
+    },
+    SetPicks {
+        participant: ParticipantIndex,
+        round: RoundIndex,
+        picks: usize,
+    },
+    AddBucket(BucketDef),
+    RemoveBucket(BucketIndex),
+    SetSlots {
+        bucket_index: BucketIndex,
+        slots: usize,
+    },
+    SetSchedule {
+        round: RoundIndex,
+        schedule: RoundSchedule,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct Amendment {
+    pub(crate) change: BasisAmendment,
+    pub(crate) at: DateTime<Utc>,
+    pub(crate) lottery: Option<LotteryRecord>, //Draws the ranks of an added participant or bucket, so they can be verified like the original draw
+}
+
+impl Amendment {
+    pub fn get_change(&self) -> &BasisAmendment {
+        &self.change
+    }
+
+    pub fn get_at(&self) -> &DateTime<Utc> {
+        &self.at
+    }
+
+    pub(crate) fn censor(&mut self) {
+        if let Some(lottery) = self.lottery.as_mut() {
+            lottery.censor();
+        }
+    }
+
+    //Adds ranks for the participant or bucket this amendment added. Ranks are indexed by bucket, then round.
+    pub(crate) fn extend_ranks(
+        &self,
+        ranks: &mut Vec<Vec<Ranks>>,
+        allocation: &Allocation,
+        participant_count: usize,
+        round_count: usize,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let lottery = match (&self.change, &self.lottery) {
+            (BasisAmendment::AddParticipant(_), Some(lottery))
+            | (BasisAmendment::AddBucket(_), Some(lottery)) => lottery,
+            (BasisAmendment::AddParticipant(_), None) | (BasisAmendment::AddBucket(_), None) => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "No lottery record to draw the added ranks from.",
+                )))
+            }
+            _ => return Ok(()),
+        };
+
+        let strategy = allocation.strategy();
+        match &self.change {
+            BasisAmendment::AddParticipant(_) => {
+                let participant = participant_count - 1;
+                let placements =
+                    strategy.draw_added_participant(lottery, participant, ranks, round_count)?;
+                for (bucket_ranks, bucket_placements) in ranks.iter_mut().zip(placements) {
+                    for (round_ranks, rank) in bucket_ranks.iter_mut().zip(bucket_placements) {
+                        insert_rank(round_ranks, participant, rank);
+                    }
+                }
+            }
+            _ => {
+                let added = strategy.draw_added_bucket(
+                    lottery,
+                    participant_count,
+                    round_count,
+                    ranks.first(),
+                )?;
+                ranks.push(added);
+            }
+        }
+        Ok(())
+    }
+}
+
+//Everyone at or below the rank moves down one, so existing participants keep their order
+fn insert_rank(ranks: &mut Ranks, participant: ParticipantIndex, rank: usize) {
+    for existing in ranks.values_mut() {
+        if *existing >= rank {
+            *existing += 1;
+        }
+    }
+    ranks.insert(participant, rank);
+}
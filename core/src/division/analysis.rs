@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use rand::RngCore;
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    basis::BlockDivisionBasis,
+    ledger::Ledger,
+    lottery::{LotteryRecord, SEED_LENGTH},
+    participant::ParticipantIndex,
+    round::RoundIndex,
+    selections::Selection,
+    state::BlockDivisionState,
+};
+
+pub const MAX_RUNS: usize = 10000;
+
+//Everyone's picks for every round, submitted or assumed
+pub type Preferences = BTreeMap<RoundIndex, BTreeMap<ParticipantIndex, Vec<Option<Selection>>>>;
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub struct ParticipantOutcomes {
+    pub name: String,
+    pub expected_first_choices: f64, //Picks granted their first choice, averaged over every run
+    pub expected_buckets: f64,
+    pub bucket_probabilities: Vec<f64>, //Chance of holding each bucket at the end of the division
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub struct FairnessReport {
+    pub runs: usize,
+    pub seed: u64, //Runs are drawn from this, so the same inputs give the same report
+    pub participants: Vec<ParticipantOutcomes>,
+}
+
+impl FairnessReport {
+    fn empty(basis: &BlockDivisionBasis, runs: usize, seed: u64) -> FairnessReport {
+        FairnessReport {
+            runs: runs,
+            seed: seed,
+            participants: basis
+                .get_participant_definitions()
+                .iter()
+                .map(|participant_def| ParticipantOutcomes {
+                    name: participant_def.get_name().to_string(),
+                    expected_first_choices: 0.0,
+                    expected_buckets: 0.0,
+                    bucket_probabilities: vec![0.0; basis.get_bucket_definitions().len()],
+                })
+                .collect(),
+        }
+    }
+}
+
+//Runs the whole division with independently drawn ranks, as if every round were submitted at once with these preferences.
+//Weighted lotteries use the same weights a new division would get from this ledger.
+pub fn analyze(
+    basis: &BlockDivisionBasis,
+    ledger: &Ledger,
+    preferences: &Preferences,
+    runs: usize,
+    seed: u64,
+) -> Result<FairnessReport, Box<dyn std::error::Error>> {
+    if runs == 0 || runs > MAX_RUNS {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Runs must be between 1 and {}.", MAX_RUNS),
+        )));
+    }
+    let weights = basis.get_lottery_weights(ledger);
+    if let Some(weights) = &weights {
+        if weights.contains(&0) {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Lottery weights must be at least 1.",
+            )));
+        }
+    }
+
+    let mut template = BlockDivisionState::from_lottery(
+        basis,
+        LotteryRecord::from_seed([0; SEED_LENGTH]).with_weights(weights.clone()),
+    )?;
+    for (round, participant_selections) in preferences {
+        for (participant, selections) in participant_selections {
+            template.set_preferences(*round, *participant, selections.clone())?;
+        }
+    }
+
+    let mut seeds = ChaCha20Rng::seed_from_u64(seed);
+    let mut report = FairnessReport::empty(basis, runs, seed);
+    for _ in 0..runs {
+        let mut run_seed = [0u8; SEED_LENGTH];
+        seeds.fill_bytes(&mut run_seed);
+
+        let mut state = template.clone();
+        state.redraw(LotteryRecord::from_seed(run_seed).with_weights(weights.clone()))?;
+
+        for (participant, outcomes) in report.participants.iter_mut().enumerate() {
+            outcomes.expected_first_choices += state.first_choices(&participant) as f64;
+            for bucket_index in state.get_holdings(&participant).keys() {
+                outcomes.expected_buckets += 1.0;
+                outcomes.bucket_probabilities[*bucket_index] += 1.0;
+            }
+        }
+    }
+
+    for outcomes in report.participants.iter_mut() {
+        outcomes.expected_first_choices /= runs as f64;
+        outcomes.expected_buckets /= runs as f64;
+        for probability in outcomes.bucket_probabilities.iter_mut() {
+            *probability /= runs as f64;
+        }
+    }
+    Ok(report)
+}
+
+//The selections already submitted to a division
+pub fn submitted_preferences(state: &BlockDivisionState) -> Preferences {
+    (0..state.get_basis().get_selection_rounds().len())
+        .filter_map(|round| {
+            state
+                .get_selections()
+                .get(&round)
+                .map(|participant_selections| (round, participant_selections.clone()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::division::{bucket::BucketDef, participant::ParticipantDef};
+
+    use super::*;
+
+    fn create_basis() -> BlockDivisionBasis {
+        let bucket = |name: &str| BucketDef {
+            name: name.to_string(),
+            available_slots: 1,
+            available_ancillaries: Vec::new(),
+            start: None,
+            end: None,
+            holiday: false,
+            quota_weight: 1,
+            removed: false,
+        };
+        let participant = |name: &str| {
+            ParticipantDef::create(
+                name.to_string(),
+                format!("{}@example.com", name),
+                Vec::from([1]),
+            )
+        };
+
+        BlockDivisionBasis::create(
+            Vec::from([bucket("Popular"), bucket("Quiet")]),
+            Vec::from([participant("a"), participant("b"), participant("c")]),
+            Vec::from(["Round 1".to_string()]),
+        )
+    }
+
+    fn pick(bucket_index: usize) -> Option<Selection> {
+        Some(Selection {
+            bucket_index: bucket_index,
+            ancillaries: BTreeSet::new(),
+            fallbacks: Vec::new(),
+            state: None,
+            granted_option: None,
+            explanations: BTreeMap::new(),
+        })
+    }
+
+    #[test]
+    fn contested_bucket_splits_evenly() {
+        //a and b want the same bucket, c has the other to themselves
+        let preferences = Preferences::from([(
+            0,
+            BTreeMap::from([
+                (0, Vec::from([pick(0)])),
+                (1, Vec::from([pick(0)])),
+                (2, Vec::from([pick(1)])),
+            ]),
+        )]);
+        let report = analyze(&create_basis(), &Ledger::new(), &preferences, 400, 7)
+            .expect("Should analyze.");
+
+        let [a, b, c] = &report.participants[..] else {
+            panic!("Should be three participants.");
+        };
+        assert!(a.bucket_probabilities[0] + b.bucket_probabilities[0] == 1.0);
+        assert!((a.bucket_probabilities[0] - 0.5).abs() < 0.1);
+        assert!(a.expected_first_choices == a.bucket_probabilities[0]);
+        assert!(c.bucket_probabilities == Vec::from([0.0, 1.0]));
+        assert!(c.expected_buckets == 1.0);
+
+        let again = analyze(&create_basis(), &Ledger::new(), &preferences, 400, 7)
+            .expect("Should analyze.");
+        assert!(again == report);
+    }
+
+    #[test]
+    fn invalid_inputs_are_rejected() {
+        let basis = create_basis();
+        assert!(analyze(&basis, &Ledger::new(), &Preferences::new(), 0, 1).is_err());
+        assert!(analyze(&basis, &Ledger::new(), &Preferences::new(), MAX_RUNS + 1, 1).is_err());
+
+        let too_many_picks =
+            Preferences::from([(0, BTreeMap::from([(0, Vec::from([pick(0), pick(1)]))]))]);
+        assert!(analyze(&basis, &Ledger::new(), &too_many_picks, 1, 1).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::{
+    bucket::{AncillaryIndex, AncillaryName, BucketIndex},
+    participant::ParticipantIndex,
+};
+
+pub type SharedAncillaryIndex = usize;
+pub type AncillaryDesignations = BTreeMap<AncillaryIndex, BTreeSet<ParticipantIndex>>;
+
+#[derive(Clone, Deserialize, Serialize, Hash, PartialEq, Eq, Debug)]
+pub struct AncillaryDef {
+    pub(crate) name: AncillaryName,
+    #[serde(default = "default_capacity")]
+    pub(crate) capacity: usize, //How many participants can hold this at once, over every round
+    #[serde(default)]
+    pub(crate) eligible_participants: Option<BTreeSet<ParticipantIndex>>, //None if anyone may request it
+}
+
+fn default_capacity() -> usize {
+    1
+}
+
+//What a bucket offers at each of its ancillary indices. A plain name is the original behavior: one holder, anyone eligible.
+#[derive(Clone, Deserialize, Serialize, Hash, PartialEq, Eq, Debug)]
+#[serde(untagged)]
+pub enum BucketAncillary {
+    Named(AncillaryName),
+    Defined(AncillaryDef), //Capacity counted in this bucket only
+    Shared { shared: SharedAncillaryIndex }, //Index into the basis ancillary definitions. Capacity is pooled across every bucket offering it.
+}
+
+//Holders of the same pool compete for the same capacity
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum AncillaryPool {
+    Local(BucketIndex, AncillaryIndex),
+    Shared(SharedAncillaryIndex),
+}
+
+impl AncillaryDef {
+    pub fn is_eligible(&self, participant: &ParticipantIndex) -> bool {
+        match &self.eligible_participants {
+            Some(eligible) => eligible.contains(participant),
+            None => true,
+        }
+    }
+}
+
+//Designations stored before capacities held a single participant per ancillary
+pub(crate) fn deserialize_ancillary_designations<'de, D>(
+    deserializer: D,
+) -> Result<AncillaryDesignations, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Holders {
+        Single(ParticipantIndex),
+        Many(BTreeSet<ParticipantIndex>),
+    }
+
+    let stored = BTreeMap::<AncillaryIndex, Holders>::deserialize(deserializer)?;
+    Ok(stored
+        .into_iter()
+        .map(|(ancillary, holders)| match holders {
+            Holders::Single(participant) => (ancillary, BTreeSet::from([participant])),
+            Holders::Many(participants) => (ancillary, participants),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_ancillary_serialization() {
+        let ancillaries: Vec<BucketAncillary> = serde_json::from_str(
+            r#"["Black Butte", {"name": "Cabin", "capacity": 2}, {"shared": 0}]"#,
+        )
+        .expect("Should deserialize.");
+
+        assert!(ancillaries[0] == BucketAncillary::Named("Black Butte".to_string()));
+        assert!(
+            ancillaries[1]
+                == BucketAncillary::Defined(AncillaryDef {
+                    name: "Cabin".to_string(),
+                    capacity: 2,
+                    eligible_participants: None,
+                })
+        );
+        assert!(ancillaries[2] == BucketAncillary::Shared { shared: 0 });
+    }
+
+    #[test]
+    fn single_holder_designations_deserialize() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            #[serde(deserialize_with = "deserialize_ancillary_designations")]
+            designations: AncillaryDesignations,
+        }
+
+        let legacy: Wrapper =
+            serde_json::from_str(r#"{"designations": {"0": 2}}"#).expect("Should deserialize.");
+        let current: Wrapper = serde_json::from_str(r#"{"designations": {"0": [1, 2]}}"#)
+            .expect("Should deserialize.");
+
+        assert!(legacy.designations == AncillaryDesignations::from([(0, BTreeSet::from([2]))]));
+        assert!(current.designations == AncillaryDesignations::from([(0, BTreeSet::from([1, 2]))]));
+    }
+}
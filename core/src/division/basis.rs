@@ -1,20 +1,51 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use serde::{Deserialize, Serialize};
 
 use crate::db::division::PersistentDivision;
 
 use super::{
-    bucket::{BucketDef, BucketIndex},
+    ancillary::{AncillaryDef, AncillaryPool, BucketAncillary},
+    bucket::{AncillaryIndex, BucketDef, BucketIndex},
+    ledger::{ledger_email, ledger_weights, Ledger},
     participant::{ParticipantDef, ParticipantIndex},
-    round::{RoundIndex, RoundName},
+    round::{RoundIndex, RoundName, RoundSchedule},
+    strategy::Allocation,
 };
 
+pub const MAX_WEIGHT: u32 = 1000; //Keeps weight totals well inside what a draw can add up
+
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
 pub struct BlockDivisionBasis {
     bucket_definitions: Vec<BucketDef>,
     participant_definitions: Vec<ParticipantDef>,
     selection_round_names: Vec<RoundName>,
+    #[serde(default)]
+    ancillary_definitions: Vec<AncillaryDef>, //Ancillaries several buckets can offer from one shared capacity
+    #[serde(default)]
+    allocation: Allocation,
+    #[serde(default)]
+    weighted_lottery: bool, //Draw ranks using participant weights
+    #[serde(default)]
+    ledger_weighted: bool, //Favor participants with worse outcomes in past divisions
+    #[serde(default)]
+    round_schedules: Vec<RoundSchedule>, //Indexed like selection_round_names. Empty if every round is opened and closed by hand.
+}
+
+//One problem with a basis. The field is a path into the basis JSON, like participant_definitions[2].email.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct BasisError {
+    pub field: String,
+    pub message: String,
+}
+
+impl BasisError {
+    fn create(field: String, message: &str) -> BasisError {
+        BasisError {
+            field: field,
+            message: message.to_string(),
+        }
+    }
 }
 
 impl BlockDivisionBasis {
@@ -27,9 +58,27 @@ impl BlockDivisionBasis {
             bucket_definitions: bucket_definitions,
             participant_definitions: participant_definitions,
             selection_round_names: selection_round_names,
+            ancillary_definitions: Vec::new(),
+            allocation: Allocation::default(),
+            weighted_lottery: false,
+            ledger_weighted: false,
+            round_schedules: Vec::new(),
         }
     }
 
+    pub fn with_allocation(mut self, allocation: Allocation) -> BlockDivisionBasis {
+        self.allocation = allocation;
+        self
+    }
+
+    pub fn with_ancillary_definitions(
+        mut self,
+        ancillary_definitions: Vec<AncillaryDef>,
+    ) -> BlockDivisionBasis {
+        self.ancillary_definitions = ancillary_definitions;
+        self
+    }
+
     pub fn get_selection_rounds(&self) -> &Vec<RoundName> {
         &self.selection_round_names
     }
@@ -41,4 +90,434 @@ impl BlockDivisionBasis {
     pub fn get_participant_definitions(&self) -> &Vec<ParticipantDef> {
         &self.participant_definitions
     }
+
+    pub(crate) fn get_bucket_definitions_mut(&mut self) -> &mut Vec<BucketDef> {
+        &mut self.bucket_definitions
+    }
+
+    pub(crate) fn get_participant_definitions_mut(&mut self) -> &mut Vec<ParticipantDef> {
+        &mut self.participant_definitions
+    }
+
+    pub fn with_weighted_lottery(mut self, weighted_lottery: bool) -> BlockDivisionBasis {
+        self.weighted_lottery = weighted_lottery;
+        self
+    }
+
+    pub fn with_ledger_weighted(mut self, ledger_weighted: bool) -> BlockDivisionBasis {
+        self.ledger_weighted = ledger_weighted;
+        self
+    }
+
+    pub fn with_round_schedules(
+        mut self,
+        round_schedules: Vec<RoundSchedule>,
+    ) -> BlockDivisionBasis {
+        self.round_schedules = round_schedules;
+        self
+    }
+
+    pub fn get_round_schedule(&self, round: RoundIndex) -> RoundSchedule {
+        self.round_schedules.get(round).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn set_round_schedule(&mut self, round: RoundIndex, schedule: RoundSchedule) {
+        if self.round_schedules.len() < self.selection_round_names.len() {
+            self.round_schedules
+                .resize(self.selection_round_names.len(), RoundSchedule::default());
+        }
+        self.round_schedules[round] = schedule;
+    }
+
+    pub fn is_ledger_weighted(&self) -> bool {
+        self.ledger_weighted
+    }
+
+    //None for a uniform draw. Otherwise seniority and ledger weights, each 1 when not asked for, multiplied together.
+    //A long ledger history can't overflow the product, it only stops adding weight.
+    pub fn get_lottery_weights(&self, ledger: &Ledger) -> Option<Vec<u32>> {
+        if !self.weighted_lottery && !self.ledger_weighted {
+            return None;
+        }
+
+        let emails: Vec<&str> = self
+            .participant_definitions
+            .iter()
+            .map(|participant| participant.get_email())
+            .collect();
+        let history = ledger_weights(&emails, ledger);
+
+        Some(
+            self.participant_definitions
+                .iter()
+                .zip(history)
+                .map(|(participant, history)| {
+                    let seniority = match self.weighted_lottery {
+                        true => participant.get_weight(),
+                        false => 1,
+                    };
+                    let history = match self.ledger_weighted {
+                        true => history,
+                        false => 1,
+                    };
+                    seniority.saturating_mul(history)
+                })
+                .collect(),
+        )
+    }
+
+    //Every problem that would break the division later, empty if the basis is usable
+    pub fn validate(&self) -> Vec<BasisError> {
+        let mut errors: Vec<BasisError> = Vec::new();
+        let participant_count = self.participant_definitions.len();
+        let round_count = self.selection_round_names.len();
+
+        if round_count == 0 {
+            errors.push(BasisError::create(
+                "selection_round_names".to_string(),
+                "At least one round is needed.",
+            ));
+        }
+        if self.bucket_definitions.is_empty() {
+            errors.push(BasisError::create(
+                "bucket_definitions".to_string(),
+                "At least one bucket is needed.",
+            ));
+        }
+        if participant_count == 0 {
+            errors.push(BasisError::create(
+                "participant_definitions".to_string(),
+                "At least one participant is needed.",
+            ));
+        }
+
+        if !self.round_schedules.is_empty() && self.round_schedules.len() != round_count {
+            errors.push(BasisError::create(
+                "round_schedules".to_string(),
+                "Needs one entry for every selection round, or none.",
+            ));
+        }
+        for (index, schedule) in self.round_schedules.iter().enumerate() {
+            if let (Some(open), Some(close)) = (schedule.open, schedule.close) {
+                if close <= open {
+                    errors.push(BasisError::create(
+                        format!("round_schedules[{}].close", index),
+                        "Close must be after open.",
+                    ));
+                }
+            }
+            if !schedule.reminder_hours.is_empty() && schedule.close.is_none() {
+                errors.push(BasisError::create(
+                    format!("round_schedules[{}].reminder_hours", index),
+                    "Reminders need a close time.",
+                ));
+            }
+            if schedule.reminder_hours.contains(&0) {
+                errors.push(BasisError::create(
+                    format!("round_schedules[{}].reminder_hours", index),
+                    "Must be at least 1.",
+                ));
+            }
+        }
+
+        let mut emails: BTreeSet<String> = BTreeSet::new();
+        for (index, participant) in self.participant_definitions.iter().enumerate() {
+            let field = format!("participant_definitions[{}]", index);
+            let email = ledger_email(participant.get_email());
+            if email.is_empty() {
+                errors.push(BasisError::create(
+                    format!("{}.email", field),
+                    "Email is required.",
+                ));
+            } else if !participant.is_removed() && !emails.insert(email) {
+                errors.push(BasisError::create(
+                    format!("{}.email", field),
+                    "Email is used by an earlier participant.",
+                ));
+            }
+            if participant.get_round_picks_allowed().len() != round_count {
+                errors.push(BasisError::create(
+                    format!("{}.round_picks_allowed", field),
+                    "Needs one entry for every selection round.",
+                ));
+            }
+            if self.weighted_lottery && participant.get_weight() == 0 {
+                errors.push(BasisError::create(
+                    format!("{}.weight", field),
+                    "Weight must be at least 1 in a weighted lottery.",
+                ));
+            }
+            if self.weighted_lottery && participant.get_weight() > MAX_WEIGHT {
+                errors.push(BasisError::create(
+                    format!("{}.weight", field),
+                    &format!("Weight can be at most {}.", MAX_WEIGHT),
+                ));
+            }
+        }
+
+        for (index, definition) in self.ancillary_definitions.iter().enumerate() {
+            validate_ancillary(
+                &mut errors,
+                format!("ancillary_definitions[{}]", index),
+                definition,
+                participant_count,
+            );
+        }
+
+        for (index, bucket) in self.bucket_definitions.iter().enumerate() {
+            let field = format!("bucket_definitions[{}]", index);
+            if bucket.available_slots == 0 {
+                errors.push(BasisError::create(
+                    format!("{}.available_slots", field),
+                    "A bucket needs at least one slot.",
+                ));
+            }
+            match (bucket.start, bucket.end) {
+                (Some(start), Some(end)) if end < start => errors.push(BasisError::create(
+                    format!("{}.end", field),
+                    "End is before start.",
+                )),
+                (Some(_), None) | (None, Some(_)) => errors.push(BasisError::create(
+                    field.clone(),
+                    "Give both start and end, or neither.",
+                )),
+                _ => {}
+            }
+            for (ancillary, bucket_ancillary) in bucket.available_ancillaries.iter().enumerate() {
+                let field = format!("{}.available_ancillaries[{}]", field, ancillary);
+                match bucket_ancillary {
+                    BucketAncillary::Named(_) => {}
+                    BucketAncillary::Defined(definition) => {
+                        validate_ancillary(&mut errors, field, definition, participant_count)
+                    }
+                    BucketAncillary::Shared { shared } => {
+                        if *shared >= self.ancillary_definitions.len() {
+                            errors.push(BasisError::create(
+                                format!("{}.shared", field),
+                                "No such shared ancillary definition.",
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+
+    //The same checks as validate, as a single error for callers that can't return the list
+    pub(crate) fn check(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let errors = self.validate();
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                errors
+                    .iter()
+                    .map(|error| format!("{}: {}", error.field, error.message))
+                    .collect::<Vec<String>>()
+                    .join("; "),
+            ))),
+        }
+    }
+
+    pub fn get_allocation(&self) -> &Allocation {
+        &self.allocation
+    }
+
+    pub fn get_ancillary_definitions(&self) -> &Vec<AncillaryDef> {
+        &self.ancillary_definitions
+    }
+
+    //The definition behind a bucket's ancillary index, and the pool whose capacity it draws from
+    pub(crate) fn resolve_ancillary(
+        &self,
+        bucket_index: &BucketIndex,
+        ancillary: &AncillaryIndex,
+    ) -> Option<(AncillaryDef, AncillaryPool)> {
+        let bucket_ancillary = self
+            .bucket_definitions
+            .get(*bucket_index)?
+            .available_ancillaries
+            .get(*ancillary)?;
+        let local = AncillaryPool::Local(*bucket_index, *ancillary);
+        match bucket_ancillary {
+            BucketAncillary::Named(name) => Some((
+                AncillaryDef {
+                    name: name.clone(),
+                    capacity: 1,
+                    eligible_participants: None,
+                },
+                local,
+            )),
+            BucketAncillary::Defined(definition) => Some((definition.clone(), local)),
+            BucketAncillary::Shared { shared } => Some((
+                self.ancillary_definitions.get(*shared)?.clone(),
+                AncillaryPool::Shared(*shared),
+            )),
+        }
+    }
+
+    pub fn get_ancillary_name(
+        &self,
+        bucket_index: &BucketIndex,
+        ancillary: &AncillaryIndex,
+    ) -> Option<String> {
+        self.resolve_ancillary(bucket_index, ancillary)
+            .map(|(definition, _)| definition.name)
+    }
+
+    //Every bucket ancillary drawing from the pool
+    pub(crate) fn get_pool_members(
+        &self,
+        pool: &AncillaryPool,
+    ) -> Vec<(BucketIndex, AncillaryIndex)> {
+        match pool {
+            AncillaryPool::Local(bucket_index, ancillary) => {
+                Vec::from([(*bucket_index, *ancillary)])
+            }
+            AncillaryPool::Shared(shared) => {
+                let mut retval: Vec<(BucketIndex, AncillaryIndex)> = Vec::new();
+                for (bucket_index, bucket) in self.bucket_definitions.iter().enumerate() {
+                    for (ancillary, bucket_ancillary) in
+                        bucket.available_ancillaries.iter().enumerate()
+                    {
+                        if *bucket_ancillary == (BucketAncillary::Shared { shared: *shared }) {
+                            retval.push((bucket_index, ancillary));
+                        }
+                    }
+                }
+                retval
+            }
+        }
+    }
+}
+
+fn validate_ancillary(
+    errors: &mut Vec<BasisError>,
+    field: String,
+    definition: &AncillaryDef,
+    participant_count: usize,
+) {
+    if definition.capacity == 0 {
+        errors.push(BasisError::create(
+            format!("{}.capacity", field),
+            "Capacity must be at least 1.",
+        ));
+    }
+    if let Some(eligible) = &definition.eligible_participants {
+        if eligible
+            .iter()
+            .any(|participant| *participant >= participant_count)
+        {
+            errors.push(BasisError::create(
+                format!("{}.eligible_participants", field),
+                "Refers to a participant that doesn't exist.",
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockDivisionBasis, MAX_WEIGHT};
+    use crate::division::{
+        ancillary::BucketAncillary,
+        bucket::BucketDef,
+        ledger::{Ledger, LedgerEntry},
+        participant::ParticipantDef,
+    };
+
+    fn bucket(slots: usize, ancillaries: Vec<BucketAncillary>) -> BucketDef {
+        BucketDef {
+            name: "Week".to_string(),
+            available_slots: slots,
+            available_ancillaries: ancillaries,
+            start: None,
+            end: None,
+            holiday: false,
+            quota_weight: 1,
+            removed: false,
+        }
+    }
+
+    fn participant(email: &str, round_picks_allowed: Vec<usize>) -> ParticipantDef {
+        ParticipantDef::create(
+            "Someone".to_string(),
+            email.to_string(),
+            round_picks_allowed,
+        )
+    }
+
+    #[test]
+    fn valid_basis_has_no_errors() {
+        let basis = BlockDivisionBasis::create(
+            Vec::from([bucket(
+                1,
+                Vec::from([BucketAncillary::Named("Boat".to_string())]),
+            )]),
+            Vec::from([participant("a@b.c", Vec::from([1, 1]))]),
+            Vec::from(["First".to_string(), "Second".to_string()]),
+        );
+        assert!(basis.validate().is_empty());
+        assert!(basis.check().is_ok());
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let mut basis = BlockDivisionBasis::create(
+            Vec::from([bucket(
+                0,
+                Vec::from([BucketAncillary::Shared { shared: 2 }]),
+            )]),
+            Vec::from([
+                participant("a@b.c", Vec::from([1, 1])),
+                participant(" A@B.C", Vec::from([1])).with_weight(MAX_WEIGHT + 1),
+            ]),
+            Vec::from(["First".to_string(), "Second".to_string()]),
+        )
+        .with_weighted_lottery(true);
+        basis.participant_definitions[0] = basis.participant_definitions[0].clone().with_weight(0);
+
+        let fields: Vec<String> = basis
+            .validate()
+            .into_iter()
+            .map(|error| error.field)
+            .collect();
+        assert_eq!(
+            fields,
+            Vec::from([
+                "participant_definitions[0].weight".to_string(),
+                "participant_definitions[1].email".to_string(),
+                "participant_definitions[1].round_picks_allowed".to_string(),
+                "participant_definitions[1].weight".to_string(),
+                "bucket_definitions[0].available_slots".to_string(),
+                "bucket_definitions[0].available_ancillaries[0].shared".to_string(),
+            ])
+        );
+        assert!(basis.check().is_err());
+    }
+
+    #[test]
+    fn long_history_saturates_weight() {
+        let basis = BlockDivisionBasis::create(
+            Vec::from([bucket(1, Vec::new())]),
+            Vec::from([participant("a@b.c", Vec::from([1])).with_weight(MAX_WEIGHT)]),
+            Vec::from(["First".to_string()]),
+        )
+        .with_weighted_lottery(true)
+        .with_ledger_weighted(true);
+        let entry = LedgerEntry {
+            division_id: "Every year".to_string(),
+            email: "a@b.c".to_string(),
+            name: "Someone".to_string(),
+            wins: 0,
+            losses: u32::MAX / 2,
+            holiday_buckets: 0,
+        };
+        let ledger = Ledger::from([("a@b.c".to_string(), Vec::from([entry.clone(), entry]))]);
+
+        assert!(basis.validate().is_empty());
+        assert!(basis.get_lottery_weights(&ledger) == Some(Vec::from([u32::MAX])));
+    }
 }
@@ -1,10 +1,15 @@
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    basis::BlockDivisionBasis, participant::ParticipantIndex, round::RoundIndex,
+    ancillary::{deserialize_ancillary_designations, AncillaryDesignations, BucketAncillary},
+    basis::BlockDivisionBasis,
+    participant::ParticipantIndex,
+    round::RoundIndex,
     selections::Selection,
+    waitlist::{Release, WaitlistEntry},
 };
 
 pub type Designations = BTreeSet<ParticipantIndex>; //Map containing selected participants. Keys are rounds of the selection.
@@ -18,45 +23,72 @@ pub type AncillaryName = String;
 pub struct BucketDef {
     pub(crate) name: String,
     pub(crate) available_slots: usize, //How many participants can fit in this bucket in total
-    pub(crate) available_ancillaries: Vec<AncillaryName>, //What ancillaries are available to an individual participant in this bucket
+    pub(crate) available_ancillaries: Vec<BucketAncillary>, //What ancillaries are available to an individual participant in this bucket
+    #[serde(default)]
+    pub(crate) start: Option<NaiveDate>, //First day of the bucket, inclusive
+    #[serde(default)]
+    pub(crate) end: Option<NaiveDate>, //Last day of the bucket, inclusive
+    #[serde(default)]
+    pub(crate) holiday: bool,          //Counted in the fairness ledger
+    #[serde(default = "default_quota_weight")]
+    pub(crate) quota_weight: u32, //How much of a participant's max_buckets this bucket uses
+    #[serde(default)]
+    pub(crate) removed: bool, //Taken out after the division started. Kept so bucket indices don't shift.
+}
+
+fn default_quota_weight() -> u32 {
+    1
+}
+
+impl BucketDef {
+    //Undated buckets never conflict with anything
+    pub(crate) fn overlaps(&self, other: &BucketDef) -> bool {
+        match (self.start, self.end, other.start, other.end) {
+            (Some(start), Some(end), Some(other_start), Some(other_end)) => {
+                start <= other_end && other_start <= end
+            }
+            _ => false,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Default, PartialEq, Eq, Clone)]
 pub struct BucketState {
     pub(crate) designations: Designations, //Who is in this bucket in each round (0 is predesignation, 1 is round 1, 2 is round 2, etc.)
-    pub(crate) ancillary_designations: BTreeMap<AncillaryIndex, ParticipantIndex>,
+    #[serde(deserialize_with = "deserialize_ancillary_designations")]
+    pub(crate) ancillary_designations: AncillaryDesignations, //Who holds each ancillary, up to its capacity
     pub(crate) ranks: Option<Ranks>, //Map containing the ranks for each participant for each potential round, make an option to allow frontend censoring
+    #[serde(default)]
+    pub(crate) waitlist: Vec<WaitlistEntry>, //Outranked picks, best rank first
+    #[serde(default)]
+    pub(crate) released: BTreeMap<ParticipantIndex, Release>, //Holders who were removed or withdrew
+    #[serde(default)]
+    pub(crate) overrides: BTreeMap<ParticipantIndex, BTreeSet<AncillaryIndex>>, //Placed by an admin outside the lottery, with their ancillaries. Reapplied before every recompute.
 }
 
 impl BucketState {
     pub(crate) fn new() -> BucketState {
         BucketState {
             designations: Designations::new(),
-            ancillary_designations: BTreeMap::new(),
+            ancillary_designations: AncillaryDesignations::new(),
             ranks: Some(Ranks::new()),
+            waitlist: Vec::new(),
+            released: BTreeMap::new(),
+            overrides: BTreeMap::new(),
         }
     }
 
-    pub(crate) fn get_winners(
-        &self,
-        candidates: &BTreeSet<ParticipantIndex>,
-        winner_count: usize,
-    ) -> BTreeSet<ParticipantIndex> {
-        let mut map: BTreeMap<&usize, ParticipantIndex> = BTreeMap::new(); //automatically sorted by key
-        for candidate in candidates {
-            let rank = self.get_rank(candidate);
-            map.insert(rank, *candidate);
-        }
-
-        while map.len() as usize > winner_count {
-            map.pop_last();
-        }
-
-        let mut retval: BTreeSet<ParticipantIndex> = BTreeSet::new();
-        for participant in map.values() {
-            retval.insert(*participant);
+    //Overridden participants hold their slot and ancillaries before anyone's picks are considered
+    pub(crate) fn apply_overrides(&mut self) {
+        for (participant, ancillaries) in self.overrides.iter() {
+            self.designations.insert(*participant);
+            for ancillary in ancillaries {
+                self.ancillary_designations
+                    .entry(*ancillary)
+                    .or_default()
+                    .insert(*participant);
+            }
         }
-        retval
     }
 
     pub(crate) fn get_rank(&self, participant: &ParticipantIndex) -> &ParticipantIndex {
@@ -67,16 +99,14 @@ impl BucketState {
             .expect("Should always exist.")
     }
 
-    pub(crate) fn is_winner(
-        &self,
-        contender: &ParticipantIndex,
-        opponent: &ParticipantIndex,
-    ) -> bool {
-        if self.get_rank(contender) > self.get_rank(opponent) {
-            false
-        } else {
-            true
+    //Removes the participant's designation along with every ancillary they hold
+    pub(crate) fn release(&mut self, participant: &ParticipantIndex) {
+        self.designations.remove(participant);
+        for holders in self.ancillary_designations.values_mut() {
+            holders.remove(participant);
         }
+        self.ancillary_designations
+            .retain(|_, holders| !holders.is_empty());
     }
 }
 
@@ -96,26 +126,6 @@ impl RoundStates {
         retval
     }
 
-    pub fn ancillary_designation_is_available_for_this_round(
-        &self,
-        round: &RoundIndex,
-        ancillary_designation: &AncillaryIndex,
-    ) -> bool {
-        for n in 0..self.round_states.len() {
-            if n == *round {
-                break;
-            }
-            let state = self.get_state(&n);
-            if state
-                .ancillary_designations
-                .contains_key(ancillary_designation)
-            {
-                return false;
-            }
-        }
-        true
-    }
-
     pub fn get_state(&self, round: &usize) -> &BucketState {
         self.round_states.get(*round).expect("Should exist.")
     }
@@ -0,0 +1,360 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use super::{
+    bucket::{AncillaryIndex, BucketDef, BucketIndex},
+    participant::ParticipantIndex,
+    state::BlockDivisionState,
+};
+
+const PRODUCT_ID: &str = "-//Block Division//Results//EN";
+const MAX_LINE_OCTETS: usize = 75;
+
+struct CalendarEvent {
+    uid: String,
+    start: NaiveDate,
+    end: NaiveDate, //Inclusive, converted to the exclusive DTEND on output
+    summary: String,
+    description: String,
+}
+
+//Designations of one participant in finalized rounds, one event per bucket. Undated buckets are left out, and so is the open round, since its designations can still change.
+pub fn participant_calendar(
+    state_id: &str,
+    state: &BlockDivisionState,
+    participant: &ParticipantIndex,
+    stamp: &DateTime<Utc>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let participant_def = match state
+        .get_basis()
+        .get_participant_definitions()
+        .get(*participant)
+    {
+        Some(participant_def) => participant_def,
+        None => {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No participant {} in {}", participant, state_id),
+            )))
+        }
+    };
+
+    let mut events: Vec<CalendarEvent> = Vec::new();
+    for (bucket_index, ancillaries) in state.get_finalized_holdings(participant) {
+        let bucket = get_bucket(state, &bucket_index);
+        if let (Some(start), Some(end)) = (bucket.start, bucket.end) {
+            let mut description = state_id.to_string();
+            if !ancillaries.is_empty() {
+                description += &format!(
+                    "\nAncillaries: {}",
+                    ancillary_names(state, &bucket_index, &ancillaries)
+                );
+            }
+            events.push(CalendarEvent {
+                uid: uid(
+                    state_id,
+                    &format!("bucket-{}-participant-{}", bucket_index, participant),
+                ),
+                start,
+                end,
+                summary: bucket.name.clone(),
+                description,
+            });
+        }
+    }
+
+    Ok(write_calendar(
+        &format!("{} - {}", state_id, participant_def.get_name()),
+        &events,
+        stamp,
+    ))
+}
+
+//Every dated bucket with at least one designee in a finalized round, listing who holds it.
+pub fn division_calendar(
+    state_id: &str,
+    state: &BlockDivisionState,
+    stamp: &DateTime<Utc>,
+) -> String {
+    let participant_defs = state.get_basis().get_participant_definitions();
+    let holdings: Vec<_> = (0..participant_defs.len())
+        .map(|participant| state.get_finalized_holdings(&participant))
+        .collect();
+
+    let mut events: Vec<CalendarEvent> = Vec::new();
+    for (bucket_index, bucket) in state
+        .get_basis()
+        .get_bucket_definitions()
+        .iter()
+        .enumerate()
+    {
+        let (start, end) = match (bucket.start, bucket.end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => continue,
+        };
+
+        let mut names: Vec<&str> = Vec::new();
+        let mut lines: Vec<String> = Vec::new();
+        for (participant, participant_holdings) in holdings.iter().enumerate() {
+            if let Some(ancillaries) = participant_holdings.get(&bucket_index) {
+                let name = participant_defs[participant].get_name();
+                names.push(name);
+                match ancillaries.is_empty() {
+                    true => lines.push(name.to_string()),
+                    false => lines.push(format!(
+                        "{} ({})",
+                        name,
+                        ancillary_names(state, &bucket_index, ancillaries)
+                    )),
+                }
+            }
+        }
+        if names.is_empty() {
+            continue;
+        }
+
+        events.push(CalendarEvent {
+            uid: uid(state_id, &format!("bucket-{}", bucket_index)),
+            start,
+            end,
+            summary: format!("{}: {}", bucket.name, names.join(", ")),
+            description: lines.join("\n"),
+        });
+    }
+
+    write_calendar(state_id, &events, stamp)
+}
+
+fn get_bucket<'a>(state: &'a BlockDivisionState, bucket_index: &BucketIndex) -> &'a BucketDef {
+    state
+        .get_basis()
+        .get_bucket_definitions()
+        .get(*bucket_index)
+        .expect("Bucket should exist.")
+}
+
+fn ancillary_names<'a>(
+    state: &BlockDivisionState,
+    bucket_index: &BucketIndex,
+    ancillaries: impl IntoIterator<Item = &'a AncillaryIndex>,
+) -> String {
+    ancillaries
+        .into_iter()
+        .filter_map(|ancillary| {
+            state
+                .get_basis()
+                .get_ancillary_name(bucket_index, ancillary)
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+//UIDs stay the same between exports, so calendar clients update events instead of duplicating them.
+//Anything but ASCII letters and digits in the state id is percent-encoded, so different ids never share a UID.
+fn uid(state_id: &str, event: &str) -> String {
+    let mut encoded = String::new();
+    for byte in state_id.bytes() {
+        match byte.is_ascii_alphanumeric() {
+            true => encoded.push(byte as char),
+            false => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    format!("{}-{}@block-division", encoded, event)
+}
+
+fn write_calendar(name: &str, events: &Vec<CalendarEvent>, stamp: &DateTime<Utc>) -> String {
+    let stamp = stamp.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut lines: Vec<String> = Vec::from([
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODUCT_ID),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ]);
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", event.uid));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!(
+            "DTSTART;VALUE=DATE:{}",
+            event.start.format("%Y%m%d")
+        ));
+        lines.push(format!(
+            "DTEND;VALUE=DATE:{}",
+            (event.end + Duration::days(1)).format("%Y%m%d")
+        ));
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        lines.push(format!("DESCRIPTION:{}", escape_text(&event.description)));
+        lines.push("TRANSP:OPAQUE".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold_line(line) + "\r\n").collect()
+}
+
+//RFC 5545 section 3.3.11
+fn escape_text(text: &str) -> String {
+    let mut retval = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => retval.push_str("\\\\"),
+            ';' => retval.push_str("\\;"),
+            ',' => retval.push_str("\\,"),
+            '\n' => retval.push_str("\\n"),
+            '\r' => {}
+            c => retval.push(c),
+        }
+    }
+    retval
+}
+
+//RFC 5545 section 3.1. Lines longer than 75 octets continue on the next line after a space, without splitting a character.
+fn fold_line(line: &str) -> String {
+    let mut retval = String::new();
+    let mut line_octets: usize = 0;
+    for c in line.chars() {
+        if line_octets + c.len_utf8() > MAX_LINE_OCTETS {
+            retval.push_str("\r\n ");
+            line_octets = 1;
+        }
+        retval.push(c);
+        line_octets += c.len_utf8();
+    }
+    retval
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::TimeZone;
+
+    use crate::division::{
+        ancillary::BucketAncillary, basis::BlockDivisionBasis, bucket::BucketDef,
+        participant::ParticipantDef, selections::Selection,
+    };
+
+    use super::*;
+
+    fn create_open_state() -> BlockDivisionState {
+        let week = |name: &str, day: u32| BucketDef {
+            name: name.to_string(),
+            available_slots: 2,
+            available_ancillaries: Vec::from([BucketAncillary::Named("Black Butte".to_string())]),
+            start: NaiveDate::from_ymd_opt(2025, 1, day),
+            end: NaiveDate::from_ymd_opt(2025, 1, day + 6),
+            holiday: false,
+            quota_weight: 1,
+            removed: false,
+        };
+        let mut undated = week("Undated", 1);
+        undated.start = None;
+
+        let basis = BlockDivisionBasis::create(
+            Vec::from([week("Week 1", 6), week("Week 2", 13), undated]),
+            Vec::from([
+                ParticipantDef::create(
+                    "Smith, A".to_string(),
+                    "a@example.com".to_string(),
+                    Vec::from([2]),
+                ),
+                ParticipantDef::create(
+                    "B".to_string(),
+                    "b@example.com".to_string(),
+                    Vec::from([2]),
+                ),
+            ]),
+            Vec::from(["Round 1".to_string()]),
+        );
+
+        //Everyone gets both picks, since there are enough slots and only A wants the ancillary
+        let mut state = BlockDivisionState::new(&basis).expect("Should create.");
+        for (participant, picks) in [
+            (0, [pick(0, &[0]), pick(2, &[])]),
+            (1, [pick(0, &[]), pick(1, &[])]),
+        ] {
+            state
+                .set_preferences(0, participant, Vec::from(picks))
+                .expect("Should set.");
+        }
+        state
+    }
+
+    fn create_state() -> BlockDivisionState {
+        let mut state = create_open_state();
+        state.finalize_round(0).expect("Should finalize.");
+        state
+    }
+
+    fn pick(bucket_index: usize, ancillaries: &[usize]) -> Option<Selection> {
+        Some(Selection {
+            bucket_index: bucket_index,
+            ancillaries: ancillaries.iter().cloned().collect(),
+            fallbacks: Vec::new(),
+            state: None,
+            granted_option: None,
+            explanations: BTreeMap::new(),
+        })
+    }
+
+    fn stamp() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn participant_calendar_lists_dated_holdings() {
+        let ics =
+            participant_calendar("Summer", &create_state(), &0, &stamp()).expect("Should work.");
+        println!("{}", ics);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.matches("BEGIN:VEVENT").count() == 1); //Undated bucket is left out
+        assert!(ics.contains("X-WR-CALNAME:Summer - Smith\\, A\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20250106\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20250113\r\n"));
+        assert!(ics.contains("DESCRIPTION:Summer\\nAncillaries: Black Butte\r\n"));
+        assert!(ics.contains("UID:Summer-bucket-0-participant-0@block-division\r\n"));
+
+        assert!(participant_calendar("Summer", &create_state(), &5, &stamp()).is_err());
+
+        //The open round's designations can still change
+        let ics = participant_calendar("Summer", &create_open_state(), &0, &stamp())
+            .expect("Should work.");
+        assert!(ics.matches("BEGIN:VEVENT").count() == 0);
+        assert!(
+            division_calendar("Summer", &create_open_state(), &stamp())
+                .matches("BEGIN:VEVENT")
+                .count()
+                == 0
+        );
+    }
+
+    #[test]
+    fn division_calendar_lists_holders() {
+        let ics = division_calendar("Summer", &create_state(), &stamp());
+        println!("{}", ics);
+
+        assert!(ics.matches("BEGIN:VEVENT").count() == 2);
+        assert!(ics.contains("SUMMARY:Week 1: Smith\\, A\\, B\r\n"));
+        assert!(ics.contains("DESCRIPTION:Smith\\, A (Black Butte)\\nB\r\n"));
+        assert!(ics.contains("SUMMARY:Week 2: B\r\n"));
+    }
+
+    #[test]
+    fn uids_differ_for_different_ids() {
+        assert!(uid("A B", "bucket-0") != uid("A-B", "bucket-0"));
+        assert!(uid("A B", "bucket-0") == "A%20B-bucket-0@block-division");
+    }
+
+    #[test]
+    fn long_lines_are_folded() {
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        let folded = fold_line(&line);
+        for physical in folded.split("\r\n") {
+            assert!(physical.len() <= MAX_LINE_OCTETS);
+        }
+        assert!(folded.replace("\r\n ", "") == line);
+    }
+}
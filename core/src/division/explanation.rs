@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    bucket::{AncillaryIndex, BucketIndex},
+    participant::ParticipantIndex,
+    round::RoundIndex,
+    selections::SelectionResult,
+    swap::SwapIndex,
+    waitlist::Release,
+};
+
+//Someone holding the bucket or ancillary. The rank is None once censored.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Rival {
+    pub(crate) participant: ParticipantIndex,
+    pub(crate) rank: Option<usize>,
+}
+
+//Ranks only matter within a round, so holders from earlier rounds have none
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct AncillaryHolder {
+    pub(crate) participant: ParticipantIndex,
+    pub(crate) bucket_index: BucketIndex,
+    pub(crate) round: RoundIndex,
+    pub(crate) rank: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct AncillaryShortfall {
+    pub(crate) ancillary: AncillaryIndex,
+    pub(crate) capacity: usize,
+    pub(crate) eligible: bool,
+    pub(crate) holders: Vec<AncillaryHolder>, //Everyone in the ancillary's pool, from this round and every earlier one
+}
+
+//Why an option of a pick ended the way it did, as of when the result was decided
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub enum Explanation {
+    Granted {
+        rank: Option<usize>,
+    },
+    Outranked {
+        rank: Option<usize>,
+        holders: Vec<Rival>, //Better ranked holders of the bucket this round, best first, or by participant once censored
+    },
+    SlotsUsed {
+        slots: usize,
+        earlier_rounds: usize, //Slots designated in earlier rounds
+        holders: Vec<Rival>,   //Settled by earlier picks this round
+    },
+    AncillaryUnavailable(Vec<AncillaryShortfall>),
+    AlreadyDesignated,
+    CalendarConflict(Vec<BucketIndex>),
+    QuotaReached {
+        used: u32,
+        requested: u32,
+        max_buckets: u32,
+    },
+    Released(Release),
+    GivenUp {
+        option_index: usize, //The option the pick was promoted to from a waitlist
+    },
+    BucketRemoved,
+    SwappedAway {
+        swap_index: SwapIndex, //The accepted swap that traded the bucket away
+    },
+}
+
+impl Explanation {
+    //The result the explanation was given for. None if the option was given up rather than decided.
+    pub(crate) fn result(&self) -> Option<SelectionResult> {
+        match self {
+            Explanation::Granted { .. } => Some(SelectionResult::Confirmed),
+            Explanation::Outranked { .. } => Some(SelectionResult::RejectedOutranked),
+            Explanation::SlotsUsed { .. } => Some(SelectionResult::RejectedNoSelectionsThisRound),
+            Explanation::AncillaryUnavailable(shortfalls) => {
+                Some(SelectionResult::RejectedAncillaryUnavailable(
+                    shortfalls
+                        .iter()
+                        .map(|shortfall| shortfall.ancillary)
+                        .collect(),
+                ))
+            }
+            Explanation::AlreadyDesignated => Some(SelectionResult::RejectedAlreadyDesignated),
+            Explanation::CalendarConflict(conflicts) => {
+                Some(SelectionResult::RejectedCalendarConflict(conflicts.clone()))
+            }
+            Explanation::QuotaReached { .. } => Some(SelectionResult::RejectedQuotaReached),
+            Explanation::Released(_) => Some(SelectionResult::Released),
+            Explanation::GivenUp { .. } => None,
+            Explanation::BucketRemoved => Some(SelectionResult::RejectedBucketRemoved),
+            Explanation::SwappedAway { .. } => Some(SelectionResult::SwappedAway),
+        }
+    }
+
+    pub(crate) fn censor_ranks(&mut self) {
+        //Holders are listed best first, so their order would give the ranks away too
+        let censor = |rivals: &mut Vec<Rival>| {
+            for rival in rivals.iter_mut() {
+                rival.rank = None;
+            }
+            rivals.sort_by_key(|rival| rival.participant);
+        };
+        match self {
+            Explanation::Granted { rank } => *rank = None,
+            Explanation::Outranked { rank, holders } => {
+                *rank = None;
+                censor(holders);
+            }
+            Explanation::SlotsUsed { holders, .. } => censor(holders),
+            Explanation::AncillaryUnavailable(shortfalls) => {
+                for holder in shortfalls
+                    .iter_mut()
+                    .flat_map(|shortfall| shortfall.holders.iter_mut())
+                {
+                    holder.rank = None;
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    amendment::Amendment,
+    basis::BlockDivisionBasis,
+    bucket::{AncillaryIndex, BucketIndex},
+    lottery::LotteryRecord,
+    participant::ParticipantIndex,
+    round::{RoundIndex, ScheduledChange},
+    selections::Selection,
+    state::BlockDivisionState,
+    swap::{Holding, SwapIndex},
+    waitlist::{Release, WithdrawalPolicy},
+};
+
+//One change to a division. The stored state is what replaying every event gives.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub enum DivisionEvent {
+    Created {
+        basis: BlockDivisionBasis,
+        lottery: LotteryRecord, //Ranks are drawn from this, so replay draws the same ones
+    },
+    Imported(Box<BlockDivisionState>), //A division created before events were recorded, as of its first recorded change
+    SelectionsSubmitted {
+        participant: ParticipantIndex,
+        selections: Vec<Option<Selection>>,
+    },
+    RoundOpened(Option<RoundIndex>),
+    RoundClosed(RoundIndex),
+    ScheduleApplied(ScheduledChange),
+    Withdrawn {
+        round: RoundIndex,
+        bucket_index: BucketIndex,
+        participant: ParticipantIndex,
+        release: Release,
+    },
+    SwapProposed {
+        proposer: ParticipantIndex,
+        proposer_holding: Holding,
+        recipient: ParticipantIndex,
+        recipient_holding: Holding,
+        at: DateTime<Utc>,
+    },
+    SwapResolved {
+        participant: ParticipantIndex,
+        swap_index: SwapIndex,
+        accept: bool,
+        at: DateTime<Utc>,
+    },
+    WithdrawalPolicySet(WithdrawalPolicy),
+    OverrideApplied {
+        round: RoundIndex,
+        bucket_index: BucketIndex,
+        participant: ParticipantIndex,
+        ancillaries: BTreeSet<AncillaryIndex>,
+        at: DateTime<Utc>,
+    },
+    OverrideRemoved {
+        round: RoundIndex,
+        bucket_index: BucketIndex,
+        participant: ParticipantIndex,
+        at: DateTime<Utc>,
+    },
+    Amended(Amendment), //Includes the lottery drawn for an addition
+    Deleted,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct EventRecord {
+    pub(crate) at: DateTime<Utc>,
+    pub(crate) event: DivisionEvent,
+}
+
+impl EventRecord {
+    pub fn create(at: DateTime<Utc>, event: DivisionEvent) -> EventRecord {
+        EventRecord {
+            at: at,
+            event: event,
+        }
+    }
+
+    pub fn get_at(&self) -> &DateTime<Utc> {
+        &self.at
+    }
+
+    pub fn get_event(&self) -> &DivisionEvent {
+        &self.event
+    }
+}
+
+//The state as of the given time, from events oldest first. None if the division didn't exist then.
+//A division deleted and created again under the same id starts over from its new Created event.
+pub fn replay(
+    events: &Vec<EventRecord>,
+    at: &DateTime<Utc>,
+) -> Result<Option<BlockDivisionState>, Box<dyn std::error::Error>> {
+    let mut state: Option<BlockDivisionState> = None;
+    for record in events.iter().take_while(|record| record.at <= *at) {
+        match &record.event {
+            DivisionEvent::Created { basis, lottery } => {
+                state = Some(BlockDivisionState::from_lottery(basis, lottery.clone())?)
+            }
+            DivisionEvent::Imported(imported) => state = Some(*imported.clone()),
+            DivisionEvent::Deleted => state = None,
+            event => match state.as_mut() {
+                Some(state) => state.replay(event)?,
+                None => {
+                    return Err(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "{:?} was recorded at {} before the division existed.",
+                            event, record.at
+                        ),
+                    )))
+                }
+            },
+        }
+    }
+    Ok(state)
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    participant::ParticipantIndex, selections::SelectionResult, state::BlockDivisionState,
+};
+
+//One participant's outcome in one completed division. Keyed by email so history follows people between divisions.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct LedgerEntry {
+    pub division_id: String,
+    pub email: String,
+    pub name: String,
+    pub wins: u32,            //Picks that were confirmed
+    pub losses: u32,          //Picks lost to someone else, or to a full bucket or ancillary
+    pub holiday_buckets: u32, //Holiday buckets held at the end of the division
+}
+
+pub type Ledger = BTreeMap<String, Vec<LedgerEntry>>; //Entries for each email, as ledger_email gives it
+
+//Emails are compared the way basis validation compares them, so a change of case doesn't lose someone's history
+pub fn ledger_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+//Only finalized rounds count, so call this once every round is closed
+pub fn entries_from_state(division_id: &str, state: &BlockDivisionState) -> Vec<LedgerEntry> {
+    let basis = state.get_basis();
+    let mut retval: Vec<LedgerEntry> = Vec::new();
+
+    for (participant, participant_def) in basis.get_participant_definitions().iter().enumerate() {
+        let mut entry = LedgerEntry {
+            division_id: division_id.to_string(),
+            email: ledger_email(participant_def.get_email()),
+            name: participant_def.get_name().to_string(),
+            wins: 0,
+            losses: 0,
+            holiday_buckets: 0,
+        };
+
+        for round in 0..basis.get_selection_rounds().len() {
+            if !state.is_finalized(&round) {
+                continue;
+            }
+            for selection in picks(state, &round, &participant) {
+                match &selection.state {
+                    //A swap trades a win for a win, and the one received isn't a pick
+                    Some(SelectionResult::Confirmed) | Some(SelectionResult::SwappedAway) => {
+                        entry.wins += 1
+                    }
+                    Some(SelectionResult::RejectedOutranked)
+                    | Some(SelectionResult::RejectedNoSelectionsThisRound)
+                    | Some(SelectionResult::RejectedAncillaryUnavailable(_)) => entry.losses += 1,
+                    _ => {} //Conflicts with the participant's own picks aren't bad luck
+                }
+            }
+        }
+
+        for bucket_index in state.get_holdings(&participant).keys() {
+            if basis.get_bucket_definitions()[*bucket_index].holiday {
+                entry.holiday_buckets += 1;
+            }
+        }
+
+        retval.push(entry);
+    }
+
+    retval
+}
+
+fn picks<'a>(
+    state: &'a BlockDivisionState,
+    round: &usize,
+    participant: &ParticipantIndex,
+) -> impl Iterator<Item = &'a super::selections::Selection> {
+    state
+        .get_selections()
+        .get(round)
+        .and_then(|participant_selections| participant_selections.get(participant))
+        .into_iter()
+        .flatten()
+        .flatten()
+}
+
+//Lottery weight from past divisions: 1, plus every past loss, plus how many fewer holiday buckets this person got than whoever got the most.
+//Weights only shift the odds, so a bad history never guarantees a good rank.
+pub fn ledger_weights(emails: &Vec<&str>, ledger: &Ledger) -> Vec<u32> {
+    let totals: Vec<(u32, u32)> = emails
+        .iter()
+        .map(|email| match ledger.get(&ledger_email(email)) {
+            Some(entries) => entries
+                .iter()
+                .fold((0u32, 0u32), |(losses, holidays), entry| {
+                    (
+                        losses.saturating_add(entry.losses),
+                        holidays.saturating_add(entry.holiday_buckets),
+                    )
+                }),
+            None => (0, 0),
+        })
+        .collect();
+    let most_holidays = totals
+        .iter()
+        .map(|(_, holidays)| *holidays)
+        .max()
+        .unwrap_or(0);
+
+    totals
+        .iter()
+        .map(|(losses, holidays)| {
+            1u32.saturating_add(*losses)
+                .saturating_add(most_holidays - holidays)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(email: &str, losses: u32, holiday_buckets: u32) -> LedgerEntry {
+        LedgerEntry {
+            division_id: "Last year".to_string(),
+            email: email.to_string(),
+            name: email.to_string(),
+            wins: 0,
+            losses,
+            holiday_buckets,
+        }
+    }
+
+    #[test]
+    fn worse_history_gets_more_weight() {
+        let ledger = Ledger::from([
+            ("a".to_string(), Vec::from([entry("a", 0, 2)])),
+            (
+                "b".to_string(),
+                Vec::from([entry("b", 3, 0), entry("b", 1, 1)]),
+            ),
+        ]);
+
+        let weights = ledger_weights(&Vec::from(["a", "b", "new"]), &ledger);
+        assert!(weights == Vec::from([1, 1 + 4 + 1, 1 + 2]));
+
+        //A change of case or stray whitespace is still the same person
+        let weights = ledger_weights(&Vec::from(["A", " B "]), &ledger);
+        assert!(weights == Vec::from([1, 1 + 4 + 1]));
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::BTreeSet;
+
+use rand::{thread_rng, RngCore};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use serde::{Deserialize, Serialize};
+
+use super::{bucket::Ranks, participant::ParticipantIndex};
+
+pub(crate) const SEED_LENGTH: usize = 32;
+
+//Named and versioned so a stored seed always re-derives the same ranks, even if the default algorithm changes later.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LotteryAlgorithm {
+    ChaCha20V1, //ChaCha20 keyed by the 32 byte seed, u64 outputs, rejection sampling for uniform draws
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct LotteryRecord {
+    algorithm: LotteryAlgorithm,
+    seed: Option<String>, //Hex encoded. Make an option to allow frontend censoring, as the seed reveals every rank.
+    #[serde(default)]
+    weights: Option<Vec<u32>>, //Participant weights at the time of the draw, or None for a uniform draw
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct RankMismatch {
+    pub bucket_index: usize,
+    pub round_index: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct RankVerification {
+    pub algorithm: LotteryAlgorithm,
+    pub seed: String,
+    pub weights: Option<Vec<u32>>,
+    pub matches: bool,
+    pub mismatches: Vec<RankMismatch>,
+}
+
+impl LotteryRecord {
+    pub fn random() -> LotteryRecord {
+        let mut seed = [0u8; SEED_LENGTH];
+        thread_rng().fill_bytes(&mut seed);
+        LotteryRecord::from_seed(seed)
+    }
+
+    pub fn from_seed(seed: [u8; SEED_LENGTH]) -> LotteryRecord {
+        LotteryRecord {
+            algorithm: LotteryAlgorithm::ChaCha20V1,
+            seed: Some(encode_seed(&seed)),
+            weights: None,
+        }
+    }
+
+    //Each participant's chance of the next best rank is proportional to their weight, so higher weights get better odds but no guarantees
+    pub fn with_weights(mut self, weights: Option<Vec<u32>>) -> LotteryRecord {
+        self.weights = weights;
+        self
+    }
+
+    pub fn get_weights(&self) -> &Option<Vec<u32>> {
+        &self.weights
+    }
+
+    pub fn get_algorithm(&self) -> &LotteryAlgorithm {
+        &self.algorithm
+    }
+
+    pub fn get_seed(&self) -> &Option<String> {
+        &self.seed
+    }
+
+    pub(crate) fn censor(&mut self) {
+        self.seed = None;
+    }
+
+    pub(crate) fn rng(&self) -> Result<LotteryRng, Box<dyn std::error::Error>> {
+        let seed = match &self.seed {
+            Some(seed) => decode_seed(seed)?,
+            None => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Lottery seed has been censored.",
+                )))
+            }
+        };
+
+        if let Some(weights) = &self.weights {
+            if weights.iter().any(|weight| *weight == 0) {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Lottery weights must be at least 1.",
+                )));
+            }
+        }
+
+        match self.algorithm {
+            LotteryAlgorithm::ChaCha20V1 => Ok(LotteryRng {
+                inner: ChaCha20Rng::from_seed(seed),
+                weights: self.weights.clone(),
+            }),
+        }
+    }
+
+    //Ranks indexed by bucket, then round. Draw order is round, then bucket, then participant, and must not change for a given algorithm.
+    pub(crate) fn draw_ranks(
+        &self,
+        participant_count: usize,
+        bucket_count: usize,
+        round_count: usize,
+    ) -> Result<Vec<Vec<Ranks>>, Box<dyn std::error::Error>> {
+        let mut rng = self.rng()?;
+
+        let mut retval: Vec<Vec<Ranks>> = Vec::new();
+        for _ in 0..bucket_count {
+            retval.push(Vec::new());
+        }
+
+        for _round in 0..round_count {
+            for bucket_ranks in retval.iter_mut() {
+                bucket_ranks.push(rng.permutation(participant_count));
+            }
+        }
+
+        Ok(retval)
+    }
+}
+
+pub(crate) struct LotteryRng {
+    inner: ChaCha20Rng,
+    weights: Option<Vec<u32>>,
+}
+
+impl LotteryRng {
+    //Ranks 1 to participant_count, each participant in turn taking a uniformly random remaining rank
+    pub(crate) fn permutation(&mut self, participant_count: usize) -> Ranks {
+        if let Some(weights) = self.weights.clone() {
+            return self.weighted_permutation(participant_count, &weights);
+        }
+
+        let mut available_ranks: BTreeSet<usize> = (1..participant_count + 1).collect();
+        let mut retval = Ranks::new();
+
+        for participant in 0..participant_count {
+            let available_ranks_as_vec: Vec<usize> = available_ranks.iter().cloned().collect();
+            let rank = available_ranks_as_vec[self.below(available_ranks_as_vec.len())];
+            available_ranks.remove(&rank);
+            retval.insert(participant as ParticipantIndex, rank);
+        }
+
+        retval
+    }
+
+    //Each rank in turn, best first, goes to a remaining participant drawn with probability proportional to their weight.
+    //Participants without a recorded weight count as weight 1.
+    fn weighted_permutation(&mut self, participant_count: usize, weights: &Vec<u32>) -> Ranks {
+        let mut remaining: Vec<(ParticipantIndex, u64)> = (0..participant_count)
+            .map(|participant| {
+                (
+                    participant as ParticipantIndex,
+                    *weights.get(participant).unwrap_or(&1) as u64,
+                )
+            })
+            .collect();
+        let mut retval = Ranks::new();
+
+        for rank in 1..participant_count + 1 {
+            let total: u64 = remaining.iter().map(|(_, weight)| weight).sum();
+            let mut draw = self.below(total as usize) as u64;
+            let mut chosen = remaining.len() - 1;
+            for (n, (_, weight)) in remaining.iter().enumerate() {
+                if draw < *weight {
+                    chosen = n;
+                    break;
+                }
+                draw -= weight;
+            }
+            let (participant, _) = remaining.remove(chosen);
+            retval.insert(participant, rank);
+        }
+
+        retval
+    }
+
+    //Rank for a participant joining an existing order, which keeps its order. Each rank in turn, best first, goes to them with probability
+    //proportional to their weight against everyone not yet placed. Without weights every rank is equally likely.
+    pub(crate) fn insertion_rank(
+        &mut self,
+        participant: ParticipantIndex,
+        existing: &Ranks,
+    ) -> usize {
+        let weights = match self.weights.clone() {
+            Some(weights) => weights,
+            None => return self.below(existing.len() + 1) + 1,
+        };
+        let weight =
+            |participant: &ParticipantIndex| *weights.get(*participant).unwrap_or(&1) as u64;
+
+        let mut order: Vec<(usize, ParticipantIndex)> = existing
+            .iter()
+            .map(|(participant, rank)| (*rank, *participant))
+            .collect();
+        order.sort();
+        let own = weight(&participant);
+        let mut remaining: u64 = order.iter().map(|(_, placed)| weight(placed)).sum();
+        for (n, (_, placed)) in order.iter().enumerate() {
+            if (self.below((own + remaining) as usize) as u64) < own {
+                return n + 1;
+            }
+            remaining -= weight(placed);
+        }
+        order.len() + 1
+    }
+
+    //Uniform in 0..bound. Rejection sampling keeps this free of modulo bias and independent of the rand crate's sampling internals.
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        let bound = bound as u64;
+        let zone = u64::MAX - (u64::MAX % bound);
+        loop {
+            let value = self.inner.next_u64();
+            if value < zone {
+                return (value % bound) as usize;
+            }
+        }
+    }
+}
+
+fn encode_seed(seed: &[u8; SEED_LENGTH]) -> String {
+    seed.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_seed(seed: &str) -> Result<[u8; SEED_LENGTH], Box<dyn std::error::Error>> {
+    if seed.len() != SEED_LENGTH * 2 || !seed.is_ascii() {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Lottery seed {} is not {} hex bytes.", seed, SEED_LENGTH),
+        )));
+    }
+
+    let mut retval = [0u8; SEED_LENGTH];
+    for (n, byte) in retval.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&seed[n * 2..n * 2 + 2], 16)?;
+    }
+    Ok(retval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_round_trip() {
+        let seed: [u8; SEED_LENGTH] = core::array::from_fn(|n| (n * 7) as u8);
+        let record = LotteryRecord::from_seed(seed);
+        let decoded = decode_seed(record.get_seed().as_ref().expect("Should be some."))
+            .expect("Should decode.");
+        assert_eq!(seed, decoded);
+    }
+
+    #[test]
+    fn draws_are_reproducible_permutations() {
+        let record = LotteryRecord::random();
+
+        let first = record.draw_ranks(5, 3, 4).expect("Should draw.");
+        let second = record.draw_ranks(5, 3, 4).expect("Should draw.");
+        assert_eq!(first, second);
+
+        for bucket in first {
+            assert_eq!(bucket.len(), 4);
+            for round in bucket {
+                let ranks: BTreeSet<usize> = round.values().cloned().collect();
+                assert_eq!(ranks, (1..6).collect::<BTreeSet<usize>>());
+            }
+        }
+    }
+
+    #[test]
+    fn weights_bias_without_determining() {
+        let record =
+            LotteryRecord::from_seed([7u8; SEED_LENGTH]).with_weights(Some(Vec::from([20, 1, 1])));
+        let ranks = record.draw_ranks(3, 500, 1).expect("Should draw.");
+
+        let mut firsts = 0;
+        for bucket in ranks.iter() {
+            let round = &bucket[0];
+            assert!(
+                round.values().cloned().collect::<BTreeSet<usize>>() == BTreeSet::from([1, 2, 3])
+            );
+            if round[&0] == 1 {
+                firsts += 1;
+            }
+        }
+        //20 in 22 odds of the best rank
+        assert!(firsts > 400);
+        assert!(firsts < 500);
+
+        assert!(LotteryRecord::random()
+            .with_weights(Some(Vec::from([0, 1, 1])))
+            .draw_ranks(3, 1, 1)
+            .is_err());
+    }
+
+    #[test]
+    fn chacha20_v1_is_stable() {
+        //Pins the output of the versioned algorithm. If this fails, stored seeds will no longer verify.
+        let record = LotteryRecord::from_seed([0u8; SEED_LENGTH]);
+        let ranks = record.draw_ranks(4, 1, 1).expect("Should draw.");
+        let drawn: Vec<usize> = ranks[0][0].values().cloned().collect();
+        assert_eq!(drawn, Vec::from([3, 4, 2, 1]));
+    }
+}
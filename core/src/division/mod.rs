@@ -1,6 +1,20 @@
+pub(crate) mod amendment;
+pub(crate) mod analysis;
+pub(crate) mod ancillary;
 pub(crate) mod basis;
 pub(crate) mod bucket;
+pub(crate) mod calendar;
+pub(crate) mod explanation;
+pub(crate) mod history;
+pub(crate) mod ledger;
+pub(crate) mod lottery;
+pub(crate) mod overrides;
 pub(crate) mod participant;
+pub(crate) mod reminder;
+pub(crate) mod results;
 pub(crate) mod round;
 pub(crate) mod selections;
 pub(crate) mod state;
+pub(crate) mod strategy;
+pub(crate) mod swap;
+pub(crate) mod waitlist;
@@ -0,0 +1,39 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    bucket::{AncillaryIndex, BucketIndex},
+    participant::ParticipantIndex,
+    round::RoundIndex,
+};
+
+//An admin placing a participant in a bucket outside the lottery, or taking that placement away
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct OverrideRecord {
+    pub(crate) round: RoundIndex,
+    pub(crate) bucket_index: BucketIndex,
+    pub(crate) participant: ParticipantIndex,
+    pub(crate) ancillaries: BTreeSet<AncillaryIndex>,
+    pub(crate) assigned: bool, //False when the override was removed
+    pub(crate) at: DateTime<Utc>,
+}
+
+impl OverrideRecord {
+    pub fn get_round(&self) -> RoundIndex {
+        self.round
+    }
+
+    pub fn get_bucket_index(&self) -> BucketIndex {
+        self.bucket_index
+    }
+
+    pub fn get_participant(&self) -> ParticipantIndex {
+        self.participant
+    }
+
+    pub fn is_assigned(&self) -> bool {
+        self.assigned
+    }
+}
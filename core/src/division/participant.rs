@@ -12,6 +12,16 @@ pub struct ParticipantDef {
     name: String,
     email: String,
     round_picks_allowed: Vec<usize>,
+    #[serde(default = "default_weight")]
+    weight: u32, //Seniority weight, only used when the basis asks for a weighted lottery
+    #[serde(default)]
+    max_buckets: Option<u32>, //Most bucket weight this participant can be confirmed for over the whole division. None for no limit.
+    #[serde(default)]
+    removed: bool, //Taken out after the division started. Kept so participant indices and links don't shift.
+}
+
+fn default_weight() -> u32 {
+    1
 }
 
 impl ParticipantDef {
@@ -23,6 +33,41 @@ impl ParticipantDef {
         &self.email
     }
 
+    pub fn get_weight(&self) -> u32 {
+        self.weight
+    }
+
+    pub fn with_weight(mut self, weight: u32) -> ParticipantDef {
+        self.weight = weight;
+        self
+    }
+
+    pub fn get_max_buckets(&self) -> Option<u32> {
+        self.max_buckets
+    }
+
+    pub fn with_max_buckets(mut self, max_buckets: Option<u32>) -> ParticipantDef {
+        self.max_buckets = max_buckets;
+        self
+    }
+
+    pub fn is_removed(&self) -> bool {
+        self.removed
+    }
+
+    pub(crate) fn remove(&mut self) {
+        self.removed = true;
+    }
+
+    pub(crate) fn rename(&mut self, name: String, email: String) {
+        self.name = name;
+        self.email = email;
+    }
+
+    pub(crate) fn set_picks(&mut self, round: RoundIndex, picks: usize) {
+        self.round_picks_allowed[round] = picks;
+    }
+
     pub fn get_round_picks_allowed(&self) -> &Vec<usize> {
         &self.round_picks_allowed
     }
@@ -32,6 +77,9 @@ impl ParticipantDef {
             name: name,
             email: email,
             round_picks_allowed: round_picks_allowed,
+            weight: default_weight(),
+            max_buckets: None,
+            removed: false,
         }
     }
 }
@@ -0,0 +1,27 @@
+use super::{participant::ParticipantIndex, round::RoundIndex};
+
+//A reminder to submit picks before the open round closes
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Reminder {
+    pub round: RoundIndex,
+    pub hours_before_close: u32,
+    pub participant: ParticipantIndex,
+}
+
+impl Reminder {
+    //Key of the marker in the key-value store, set once the reminder has been sent
+    pub fn sent_key(&self, state_id: &str) -> String {
+        format!(
+            "reminder_sent/{}/{}/{}/{}",
+            state_id, self.round, self.hours_before_close, self.participant
+        )
+    }
+
+    //Key of the count of failed attempts to send the reminder
+    pub fn failed_key(&self, state_id: &str) -> String {
+        format!(
+            "reminder_failed/{}/{}/{}/{}",
+            state_id, self.round, self.hours_before_close, self.participant
+        )
+    }
+}
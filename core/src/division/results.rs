@@ -0,0 +1,270 @@
+use super::{
+    bucket::{AncillaryIndex, BucketIndex},
+    participant::ParticipantIndex,
+    round::RoundIndex,
+    selections::{Selection, SelectionResult},
+    state::BlockDivisionState,
+};
+
+//Key of the marker in the key-value store, set once a participant's results for the round have been sent
+pub fn sent_key(state_id: &str, round: RoundIndex, participant: ParticipantIndex) -> String {
+    format!("results_sent/{}/{}/{}", state_id, round, participant)
+}
+
+//Key of the count of failed attempts to send a participant's results for the round
+pub fn failed_key(state_id: &str, round: RoundIndex, participant: ParticipantIndex) -> String {
+    format!("results_failed/{}/{}/{}", state_id, round, participant)
+}
+
+//What happened to each of a participant's picks in a round, one line per pick, for the results e-mail.
+//None if the participant had no picks that round.
+pub fn round_summary(
+    state: &BlockDivisionState,
+    round: RoundIndex,
+    participant: ParticipantIndex,
+) -> Option<Vec<String>> {
+    match state
+        .get_basis()
+        .get_participant_definitions()
+        .get(participant)
+    {
+        Some(participant_def) if !participant_def.is_removed() => {}
+        _ => return None,
+    }
+    let picks = state.get_selections().get(&round)?.get(&participant)?;
+    if picks.is_empty() {
+        return None;
+    }
+    if picks.iter().all(|pick| pick.is_none()) {
+        return Some(Vec::from(["You didn't enter any picks.".to_string()]));
+    }
+
+    Some(
+        picks
+            .iter()
+            .enumerate()
+            .map(|(pick_index, pick)| {
+                let outcome = match pick {
+                    Some(selection) => (0..selection.option_count())
+                        .filter_map(|option_index| {
+                            describe_option(
+                                state,
+                                round,
+                                participant,
+                                pick_index,
+                                selection,
+                                option_index,
+                            )
+                        })
+                        .collect::<Vec<String>>()
+                        .join("; then "),
+                    None => "none entered".to_string(),
+                };
+                format!("Pick {}: {}", pick_index + 1, outcome)
+            })
+            .collect(),
+    )
+}
+
+//None if the option was never tried
+fn describe_option(
+    state: &BlockDivisionState,
+    round: RoundIndex,
+    participant: ParticipantIndex,
+    pick_index: usize,
+    selection: &Selection,
+    option_index: usize,
+) -> Option<String> {
+    let (bucket_index, _) = selection.get_option(option_index);
+    let basis = state.get_basis();
+    let bucket_name = &basis.get_bucket_definitions()[bucket_index].name;
+    let bucket_state = state.get_bucket_states()[bucket_index].get_state(&round);
+    let ancillary_names = |ancillaries: &mut dyn Iterator<Item = &AncillaryIndex>| {
+        ancillaries
+            .filter_map(|ancillary| basis.get_ancillary_name(&bucket_index, ancillary))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+
+    let description = match option_result(selection, option_index)? {
+        SelectionResult::Confirmed => {
+            let held = ancillary_names(
+                &mut bucket_state
+                    .ancillary_designations
+                    .iter()
+                    .filter(|(_, holders)| holders.contains(&participant))
+                    .map(|(ancillary, _)| ancillary),
+            );
+            match held.is_empty() {
+                true => format!("{} confirmed", bucket_name),
+                false => format!("{} confirmed with {}", bucket_name, held),
+            }
+        }
+        SelectionResult::RejectedOutranked | SelectionResult::RejectedNoSelectionsThisRound => {
+            match bucket_state.waitlist.iter().position(|entry| {
+                entry.participant == participant
+                    && entry.pick_index == pick_index
+                    && entry.option_index == option_index
+            }) {
+                Some(position) => format!(
+                    "{} outranked, number {} on the waitlist",
+                    bucket_name,
+                    position + 1
+                ),
+                None => format!("{} outranked", bucket_name),
+            }
+        }
+        SelectionResult::RejectedAncillaryUnavailable(ancillaries) => format!(
+            "{} not granted, {} unavailable",
+            bucket_name,
+            ancillary_names(&mut ancillaries.iter())
+        ),
+        SelectionResult::RejectedAlreadyDesignated => {
+            format!("{} already held from an earlier pick", bucket_name)
+        }
+        SelectionResult::RejectedCalendarConflict(conflicts) => format!(
+            "{} overlaps {}, which you already hold",
+            bucket_name,
+            bucket_names(state, &conflicts)
+        ),
+        SelectionResult::RejectedQuotaReached => format!(
+            "{} not granted, you reached your limit of buckets",
+            bucket_name
+        ),
+        SelectionResult::RejectedBucketRemoved => {
+            format!("{} was removed from the division", bucket_name)
+        }
+        SelectionResult::Released => format!("{} released", bucket_name),
+        SelectionResult::SwappedAway => format!("{} swapped away", bucket_name),
+    };
+    Some(description)
+}
+
+//The first choice's own result is overwritten once a fallback is granted, so it comes from its explanation
+fn option_result(selection: &Selection, option_index: usize) -> Option<SelectionResult> {
+    match (option_index, selection.granted_option) {
+        (0, Some(granted)) if granted > 0 => selection
+            .explanations
+            .get(&0)
+            .and_then(|explanation| explanation.result()),
+        (0, _) => selection.state.clone(),
+        (n, _) => selection.fallbacks[n - 1].state.clone(),
+    }
+}
+
+fn bucket_names(state: &BlockDivisionState, buckets: &Vec<BucketIndex>) -> String {
+    buckets
+        .iter()
+        .map(|bucket_index| {
+            state.get_basis().get_bucket_definitions()[*bucket_index]
+                .name
+                .clone()
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use crate::{
+        db::{memory::InMemoryDivisionStore, store::DivisionStore},
+        division::{
+            ancillary::BucketAncillary,
+            basis::BlockDivisionBasis,
+            bucket::{BucketDef, Ranks},
+            participant::ParticipantDef,
+            selections::{Selection, SelectionOption},
+        },
+    };
+
+    use super::*;
+
+    fn pick(bucket_index: usize, ancillaries: &[usize], fallbacks: &[usize]) -> Option<Selection> {
+        Some(Selection {
+            bucket_index: bucket_index,
+            ancillaries: ancillaries.iter().cloned().collect(),
+            fallbacks: fallbacks
+                .iter()
+                .map(|fallback| SelectionOption {
+                    bucket_index: *fallback,
+                    ancillaries: BTreeSet::new(),
+                    state: None,
+                })
+                .collect(),
+            state: None,
+            granted_option: None,
+            explanations: BTreeMap::new(),
+        })
+    }
+
+    #[test]
+    fn summary_describes_every_pick() {
+        let week = |name: &str| BucketDef {
+            name: name.to_string(),
+            available_slots: 1,
+            available_ancillaries: Vec::from([BucketAncillary::Named("Black Butte".to_string())]),
+            start: None,
+            end: None,
+            holiday: false,
+            quota_weight: 1,
+            removed: false,
+        };
+        let participant = |name: &str| {
+            ParticipantDef::create(
+                name.to_string(),
+                format!("{}@example.com", name),
+                Vec::from([1]),
+            )
+        };
+        let basis = BlockDivisionBasis::create(
+            Vec::from([week("Week 1"), week("Week 2")]),
+            Vec::from([participant("a"), participant("b"), participant("c")]),
+            Vec::from(["Round 1".to_string()]),
+        );
+        //Ranks of a, b and c, pinned in both weeks so the outcome doesn't depend on the lottery
+        let close_with_ranks = |ranks: [usize; 3]| {
+            let mut state = BlockDivisionState::new(&basis).expect("Should create.");
+            for bucket_states in state.get_bucket_states_mut().iter_mut() {
+                bucket_states.get_state_mut(&0).ranks =
+                    Some(Ranks::from([(0, ranks[0]), (1, ranks[1]), (2, ranks[2])]));
+            }
+            let mut store = InMemoryDivisionStore::new();
+            let id = "Test Results";
+            store.insert_state(id, &state).expect("Should insert.");
+            BlockDivisionState::set_open_round(&mut store, id.to_string(), Some(0))
+                .expect("Should open.");
+            for (participant, selection) in [(0, pick(0, &[0], &[1])), (1, pick(0, &[], &[]))] {
+                BlockDivisionState::set_selections_for_current_round(
+                    &mut store,
+                    id.to_string(),
+                    participant,
+                    Vec::from([selection]),
+                )
+                .expect("Should select.");
+            }
+            BlockDivisionState::close_round(&mut store, id.to_string(), 0).expect("Should close.");
+            store
+                .get_state(id)
+                .expect("Should get.")
+                .expect("Should exist.")
+        };
+        let summary = |state: &BlockDivisionState, participant| {
+            round_summary(state, 0, participant).expect("Should have a summary.")[0].clone()
+        };
+
+        let state = close_with_ranks([2, 1, 3]);
+        assert!(
+            summary(&state, 0)
+                == "Pick 1: Week 1 outranked, number 1 on the waitlist; then Week 2 confirmed"
+        );
+        assert!(summary(&state, 1) == "Pick 1: Week 1 confirmed");
+
+        let state = close_with_ranks([1, 2, 3]);
+        assert!(summary(&state, 0) == "Pick 1: Week 1 confirmed with Black Butte");
+        assert!(summary(&state, 1) == "Pick 1: Week 1 outranked, number 1 on the waitlist");
+        assert!(summary(&state, 2) == "You didn't enter any picks.");
+        assert!(round_summary(&state, 0, 3).is_none());
+    }
+}
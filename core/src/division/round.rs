@@ -1,2 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
 pub(crate) type RoundName = String;
 pub(crate) type RoundIndex = usize;
+
+//When a round opens and closes on its own. Either may be left to the admin.
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq, Eq, Clone)]
+pub struct RoundSchedule {
+    #[serde(default)]
+    pub(crate) open: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub(crate) close: Option<DateTime<Utc>>, //Finalizes the round
+    #[serde(default)]
+    pub(crate) reminder_hours: Vec<u32>, //Hours before close to remind anyone who hasn't submitted
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum ScheduleEvent {
+    Open,
+    Close,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct ScheduledChange {
+    pub round: RoundIndex,
+    pub event: ScheduleEvent,
+}
+
+impl RoundSchedule {
+    pub fn create(open: Option<DateTime<Utc>>, close: Option<DateTime<Utc>>) -> RoundSchedule {
+        RoundSchedule {
+            open: open,
+            close: close,
+            reminder_hours: Vec::new(),
+        }
+    }
+
+    pub fn with_reminder_hours(mut self, reminder_hours: Vec<u32>) -> RoundSchedule {
+        self.reminder_hours = reminder_hours;
+        self
+    }
+}
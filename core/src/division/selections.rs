@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     basis::BlockDivisionBasis,
+    bucket::{AncillaryIndex, BucketIndex},
+    explanation::Explanation,
     participant::{self, ParticipantIndex},
     round::RoundIndex,
 };
@@ -14,13 +16,103 @@ pub enum SelectionResult {
     RejectedOutranked,
     RejectedNoSelectionsThisRound,
     RejectedAncillaryUnavailable(Vec<usize>),
+    RejectedAlreadyDesignated, //An earlier pick this round already holds this bucket
+    RejectedCalendarConflict(Vec<usize>), //Buckets already held whose dates overlap this one
+    RejectedQuotaReached,      //The participant already holds their max_buckets
+    RejectedBucketRemoved,     //The bucket was taken out of the basis after the division started
+    Released,                  //Removed from the bucket after being confirmed
+    SwappedAway,               //Traded to another participant after being confirmed
+}
+
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, PartialOrd, Ord, Clone)]
+pub struct SelectionOption {
+    pub(crate) bucket_index: usize,
+    pub(crate) ancillaries: BTreeSet<usize>,
+    pub(crate) state: Option<SelectionResult>, //Only set if this option was attempted
 }
 
 #[derive(Deserialize, Serialize, Debug, Eq, PartialEq, PartialOrd, Ord, Clone)]
 pub struct Selection {
     pub(crate) bucket_index: usize,
     pub(crate) ancillaries: BTreeSet<usize>, //this is where Black Butte will go but opens it to other possibilities
-    pub(crate) state: Option<SelectionResult>,
+    #[serde(default)]
+    pub(crate) fallbacks: Vec<SelectionOption>, //Tried in order if the first choice is rejected
+    pub(crate) state: Option<SelectionResult>, //Outcome of the pick as a whole, so Confirmed if any option was confirmed
+    #[serde(default)]
+    pub(crate) granted_option: Option<usize>, //0 is the first choice, n is fallbacks[n-1]
+    #[serde(default)]
+    pub(crate) explanations: BTreeMap<usize, Explanation>, //Why each attempted option ended as it did, keyed like granted_option
+}
+
+impl SelectionResult {
+    //Lost to someone ranked higher, so worth waiting for
+    pub(crate) fn is_outranked(&self) -> bool {
+        match self {
+            SelectionResult::RejectedOutranked | SelectionResult::RejectedNoSelectionsThisRound => {
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Selection {
+    pub(crate) fn option_count(&self) -> usize {
+        self.fallbacks.len() + 1
+    }
+
+    //Bucket and ancillaries of an option, where 0 is the first choice
+    pub(crate) fn get_option(
+        &self,
+        option_index: usize,
+    ) -> (BucketIndex, &BTreeSet<AncillaryIndex>) {
+        match option_index {
+            0 => (self.bucket_index, &self.ancillaries),
+            n => {
+                let fallback = self.fallbacks.get(n - 1).expect("Option should exist.");
+                (fallback.bucket_index, &fallback.ancillaries)
+            }
+        }
+    }
+
+    pub(crate) fn clear_results(&mut self) {
+        self.state = None;
+        self.granted_option = None;
+        self.explanations.clear();
+        for fallback in self.fallbacks.iter_mut() {
+            fallback.state = None;
+        }
+    }
+
+    pub(crate) fn set_option_result(
+        &mut self,
+        option_index: usize,
+        result: SelectionResult,
+        explanation: Explanation,
+    ) {
+        self.explanations.insert(option_index, explanation);
+        match option_index {
+            0 => {}
+            n => {
+                self.fallbacks
+                    .get_mut(n - 1)
+                    .expect("Option should exist.")
+                    .state = Some(result.clone());
+            }
+        }
+
+        if result == SelectionResult::Confirmed {
+            self.granted_option = Some(option_index);
+            self.state = Some(result);
+        } else {
+            if self.granted_option == Some(option_index) {
+                self.granted_option = None; //Displaced by a higher rank, or released
+                self.state = Some(result);
+            } else if option_index == 0 || self.state.is_none() {
+                self.state = Some(result);
+            }
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Eq, PartialEq, PartialOrd, Ord, Clone)]
@@ -86,20 +178,35 @@ impl Selections {
         };
         round_selections.insert(participant, selections);
     }
+
+    pub(crate) fn set_round(
+        &mut self,
+        round: RoundIndex,
+        selections: BTreeMap<ParticipantIndex, Vec<Option<Selection>>>,
+    ) {
+        self.state.insert(round, selections);
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeSet;
+    use std::collections::{BTreeMap, BTreeSet};
 
-    use super::{Selection, SelectionResult};
+    use super::{Selection, SelectionOption, SelectionResult};
 
     #[test]
     fn selection_serialization() {
         let s = Selection {
             bucket_index: 35,
             ancillaries: BTreeSet::from([15, 22, 18]),
+            fallbacks: Vec::from([SelectionOption {
+                bucket_index: 36,
+                ancillaries: BTreeSet::new(),
+                state: None,
+            }]),
             state: None,
+            granted_option: None,
+            explanations: BTreeMap::new(),
         };
 
         let str = serde_json::to_string(&s).expect("Should serialize.");
@@ -109,6 +216,16 @@ mod tests {
         assert!(s == res);
     }
 
+    #[test]
+    fn selection_without_fallbacks_deserializes() {
+        //Selections saved before fallbacks existed
+        let str = r#"{"bucket_index":3,"ancillaries":[0],"state":"Confirmed"}"#;
+        let s: Selection = serde_json::from_str(str).expect("Should deserialize.");
+        assert!(s.fallbacks.is_empty());
+        assert!(s.granted_option.is_none());
+        assert!(s.option_count() == 1);
+    }
+
     #[test]
     fn result_serialization() {
         let res = [
@@ -116,6 +233,8 @@ mod tests {
             SelectionResult::RejectedOutranked,
             SelectionResult::RejectedNoSelectionsThisRound,
             SelectionResult::RejectedAncillaryUnavailable(Vec::from([0, 1, 2])),
+            SelectionResult::RejectedAlreadyDesignated,
+            SelectionResult::RejectedCalendarConflict(Vec::from([3])),
         ];
 
         for r in res {
@@ -1,6 +1,4 @@
 use chrono::round;
-use diesel::PgConnection;
-use rand::prelude::*;
 
 use std::{
     borrow::BorrowMut,
@@ -12,16 +10,29 @@ use std::{
     thread::current,
 };
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::db::division::PersistentDivision;
+use crate::db::store::DivisionStore;
 
 use super::{
+    amendment::{Amendment, BasisAmendment},
     basis::BlockDivisionBasis,
-    bucket::{self, BucketDef, BucketIndex, BucketState, BucketStates, Ranks, RoundStates},
+    bucket::{
+        self, AncillaryIndex, BucketDef, BucketIndex, BucketState, BucketStates, Designations,
+        Ranks, RoundStates,
+    },
+    explanation::{AncillaryHolder, AncillaryShortfall, Explanation, Rival},
+    history::{self, DivisionEvent, EventRecord},
+    ledger::{self, Ledger},
+    lottery::{LotteryRecord, RankMismatch, RankVerification},
+    overrides::OverrideRecord,
     participant::{ParticipantDef, ParticipantIndex},
-    round::{RoundIndex, RoundName},
-    selections::{Selection, SelectionResult, Selections},
+    reminder::Reminder,
+    round::{RoundIndex, RoundName, ScheduleEvent, ScheduledChange},
+    selections::{Selection, SelectionOption, SelectionResult, Selections},
+    swap::{Holding, SwapIndex, SwapProposal, SwapStatus},
+    waitlist::{Promotion, Release, Releaser, WaitlistEntry, WithdrawalPolicy},
 };
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
@@ -30,6 +41,20 @@ pub struct BlockDivisionState {
     bucket_states: BucketStates,
     selections: Selections,
     current_open_round: Option<RoundIndex>,
+    #[serde(default)]
+    lottery: Option<LotteryRecord>, //None for divisions drawn before ranks were seeded
+    #[serde(default)]
+    finalized_rounds: BTreeSet<RoundIndex>, //Designations and selection results of these rounds never change, except by withdrawal
+    #[serde(default)]
+    withdrawal_policy: WithdrawalPolicy,
+    #[serde(default)]
+    swaps: Vec<SwapProposal>,
+    #[serde(default)]
+    amendments: Vec<Amendment>, //Changes to the basis since the division was created, in order
+    #[serde(default)]
+    override_log: Vec<OverrideRecord>, //Every admin override and removal, in order
+    #[serde(default)]
+    schedule_applied: BTreeSet<ScheduledChange>, //Scheduled opens and closes already carried out, so none runs twice
 }
 
 #[derive(Deserialize, Serialize)]
@@ -50,11 +75,68 @@ impl BlockDivisionState {
         &self.current_open_round
     }
 
+    pub fn get_lottery(&self) -> &Option<LotteryRecord> {
+        &self.lottery
+    }
+
+    pub fn get_selections(&self) -> &Selections {
+        &self.selections
+    }
+
+    pub fn get_withdrawal_policy(&self) -> WithdrawalPolicy {
+        self.withdrawal_policy
+    }
+
+    pub fn get_swaps(&self) -> &Vec<SwapProposal> {
+        &self.swaps
+    }
+
+    pub fn get_amendments(&self) -> &Vec<Amendment> {
+        &self.amendments
+    }
+
+    pub fn get_override_log(&self) -> &Vec<OverrideRecord> {
+        &self.override_log
+    }
+
+    pub fn get_finalized_rounds(&self) -> &BTreeSet<RoundIndex> {
+        &self.finalized_rounds
+    }
+
+    pub fn get_schedule_applied(&self) -> &BTreeSet<ScheduledChange> {
+        &self.schedule_applied
+    }
+
+    pub fn get_bucket_states(&self) -> &BucketStates {
+        &self.bucket_states
+    }
+
     pub fn get_bucket_states_mut(&mut self) -> &mut BucketStates {
         &mut self.bucket_states
     }
 
-    pub fn new(basis: &BlockDivisionBasis) -> BlockDivisionState {
+    pub fn new(
+        basis: &BlockDivisionBasis,
+    ) -> Result<BlockDivisionState, Box<dyn std::error::Error>> {
+        BlockDivisionState::new_with_ledger(basis, &Ledger::new())
+    }
+
+    //The ledger is only used if the basis asks for ledger weighting
+    pub fn new_with_ledger(
+        basis: &BlockDivisionBasis,
+        ledger: &Ledger,
+    ) -> Result<BlockDivisionState, Box<dyn std::error::Error>> {
+        BlockDivisionState::from_lottery(
+            basis,
+            LotteryRecord::random().with_weights(basis.get_lottery_weights(ledger)), //Recorded so the draw can be audited even if weights change later
+        )
+    }
+
+    //Fails if the lottery can't draw, such as when its seed has been censored or a weight is 0
+    pub fn from_lottery(
+        basis: &BlockDivisionBasis,
+        lottery: LotteryRecord,
+    ) -> Result<BlockDivisionState, Box<dyn std::error::Error>> {
         let mut bucket_states: BucketStates = Vec::new();
         for bucket_index in 0..basis.get_bucket_definitions().len() {
             bucket_states.insert(bucket_index, RoundStates::new(basis));
@@ -65,501 +147,3831 @@ impl BlockDivisionState {
             bucket_states: bucket_states,
             selections: Selections::new(basis),
             current_open_round: None,
+            lottery: Some(lottery),
+            finalized_rounds: BTreeSet::new(),
+            withdrawal_policy: WithdrawalPolicy::default(),
+            swaps: Vec::new(),
+            amendments: Vec::new(),
+            override_log: Vec::new(),
+            schedule_applied: BTreeSet::new(),
         };
 
-        retval.generate_ranks(); //Only generate ranks here. This should only happen once per basis.
+        retval.generate_ranks(true)?; //Only generate ranks here. This should only happen once per basis.
 
-        retval
+        Ok(retval)
     }
 
     pub fn set_selections_for_current_round(
-        conn: &mut PgConnection,
+        store: &mut dyn DivisionStore,
         state_id: String,
         participant_index: ParticipantIndex,
         selections: Vec<Option<Selection>>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        match PersistentDivision::get_from_id(conn, &state_id) {
-            Some(pd) => match pd.as_state() {
-                Ok(mut state) => match state.current_open_round {
-                    Some(current_open_round) => {
-                        let pick_count = state
-                            .basis
-                            .get_participant_definitions()
-                            .get(participant_index)
-                            .expect("Participant should exist.")
-                            .get_round_picks_allowed()
-                            .get(current_open_round)
-                            .expect("Round should exist.");
-
-                        if selections.len() != (*pick_count as usize) {
-                            Err(Box::new(std::io::Error::new(
-                                std::io::ErrorKind::InvalidInput,
-                                format!(
-                                    "Incorrect number of picks for {}. Ignoring selection input.",
-                                    participant_index
-                                ),
-                            )))
-                        } else {
-                            state
-                                .selections
-                                .set(current_open_round, participant_index, selections);
-
-                            state.determine_designations_from_current_selections();
-
-                            state.save_state(state_id, conn)
-                        }
-                    }
-                    None => Err(Box::new(std::io::Error::new(
-                        std::io::ErrorKind::InvalidInput,
-                        format!("Selections are closed."),
-                    ))),
-                },
-                Err(e) => Err(e),
-            },
+        let event = DivisionEvent::SelectionsSubmitted {
+            participant: participant_index,
+            selections: selections.clone(),
+        };
+        BlockDivisionState::modify_in_store(store, state_id, event, |state| {
+            state.apply_selections(participant_index, selections)?;
+            Ok(())
+        })
+    }
+
+    //Projected results of the participant's picks in the open round if they submitted these selections. Nothing is saved.
+    //Explanations are censored, since anyone can simulate for any participant.
+    pub fn simulate_selections(
+        &self,
+        participant_index: ParticipantIndex,
+        selections: Vec<Option<Selection>>,
+    ) -> Result<Vec<Option<Selection>>, Box<dyn std::error::Error>> {
+        let mut projected = self.clone();
+        let round = projected.apply_selections(participant_index, selections)?;
+        projected.censor_explanations(&round);
+        match projected
+            .selections
+            .get(&round)
+            .and_then(|round_selections| round_selections.get(&participant_index))
+        {
+            Some(projected) => Ok(projected.clone()),
             None => Err(Box::new(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
-                format!("No state with id {}", state_id),
+                format!("No selections for {} in round {}", participant_index, round),
             ))),
         }
     }
 
-    fn determine_designations_from_current_selections(&mut self) {
-        struct SelectionInstance {
-            rank: usize,
-            round: RoundIndex,
-            participant: ParticipantIndex,
-            selection_index: usize,
-            selection: Selection,
+    //Validates and records the participant's selections for the open round, then recomputes. Returns the open round.
+    fn apply_selections(
+        &mut self,
+        participant_index: ParticipantIndex,
+        selections: Vec<Option<Selection>>,
+    ) -> Result<RoundIndex, Box<dyn std::error::Error>> {
+        let current_open_round = match self.current_open_round {
+            Some(current_open_round) if self.is_finalized(&current_open_round) => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Round {} is finalized.", current_open_round),
+                )))
+            }
+            Some(current_open_round) => current_open_round,
+            None => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Selections are closed.",
+                )))
+            }
+        };
+
+        self.check_selections(current_open_round, participant_index, &selections)?;
+        self.selections
+            .set(current_open_round, participant_index, selections);
+        self.determine_designations_from_current_selections();
+        Ok(current_open_round)
+    }
+
+    fn check_selections(
+        &self,
+        round: RoundIndex,
+        participant_index: ParticipantIndex,
+        selections: &Vec<Option<Selection>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.check_round_index(round)?;
+        let pick_count = match self
+            .basis
+            .get_participant_definitions()
+            .get(participant_index)
+        {
+            Some(participant_def) if participant_def.is_removed() => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Participant {} has been removed.", participant_index),
+                )))
+            }
+            Some(participant_def) => *participant_def
+                .get_round_picks_allowed()
+                .get(round)
+                .expect("Round should exist."),
+            None => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("No participant {}", participant_index),
+                )))
+            }
+        };
+
+        if selections.len() != pick_count {
+            Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Incorrect number of picks for {}. Ignoring selection input.",
+                    participant_index
+                ),
+            )))
+        } else if let Some(invalid) = self.find_invalid_option(&participant_index, selections) {
+            Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "{} for {}. Ignoring selection input.",
+                    invalid, participant_index
+                ),
+            )))
+        } else {
+            Ok(())
         }
+    }
 
-        let mut selections_to_attempt: Vec<SelectionInstance> = Vec::new();
+    //Records picks for any round without recomputing, for analysis of assumed preferences
+    pub(crate) fn set_preferences(
+        &mut self,
+        round: RoundIndex,
+        participant_index: ParticipantIndex,
+        selections: Vec<Option<Selection>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.check_selections(round, participant_index, &selections)?;
+        self.selections.set(round, participant_index, selections);
+        Ok(())
+    }
 
-        for round in 0..self.basis.get_selection_rounds().len() {
-            println!("Getting selections for round {}", round);
-
-            match self.selections.get(&round) {
-                Some(participant_selections_map) => {
-                    for (participant, selections) in participant_selections_map {
-                        for selection in selections.iter() {
-                            match selection {
-                                Some(selection) => {
-                                    let rank = self
-                                        .bucket_states
-                                        .get(selection.bucket_index)
-                                        .expect("Should exist.")
-                                        .get_state(&round)
-                                        .get_rank(participant);
-
-                                    selections_to_attempt.push(SelectionInstance {
-                                        rank: *rank,
-                                        round: round,
-                                        participant: participant.clone(),
-                                        selection_index: round,
-                                        selection: selection.clone(),
-                                    });
-                                }
-                                None => { //Do Nothing}
-                                }
+    //Replaces the lottery and recomputes every round that isn't finalized with the new ranks.
+    //Quiet, since analysis redraws thousands of times.
+    pub(crate) fn redraw(
+        &mut self,
+        lottery: LotteryRecord,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.lottery = Some(lottery);
+        self.generate_ranks(false)?;
+        self.determine_designations(false);
+        Ok(())
+    }
+
+    //Picks in any round that were granted their first choice
+    pub(crate) fn first_choices(&self, participant: &ParticipantIndex) -> usize {
+        (0..self.basis.get_selection_rounds().len())
+            .filter_map(|round| self.selections.get(&round)?.get(participant))
+            .flatten()
+            .flatten()
+            .filter(|selection| selection.granted_option == Some(0))
+            .count()
+    }
+
+    //Every option of every pick must name an existing bucket and ancillaries that bucket offers to this participant
+    fn find_invalid_option(
+        &self,
+        participant: &ParticipantIndex,
+        selections: &Vec<Option<Selection>>,
+    ) -> Option<String> {
+        for selection in selections.iter().flatten() {
+            for option_index in 0..selection.option_count() {
+                let (bucket_index, ancillaries) = selection.get_option(option_index);
+                match self.basis.get_bucket_definitions().get(bucket_index) {
+                    Some(bucket) if bucket.removed => {
+                        return Some(format!("Bucket {} has been removed", bucket_index))
+                    }
+                    Some(_) => {}
+                    None => return Some(format!("Invalid bucket {}", bucket_index)),
+                }
+                for ancillary in ancillaries {
+                    match self.basis.resolve_ancillary(&bucket_index, ancillary) {
+                        Some((definition, _)) => {
+                            if !definition.is_eligible(participant) {
+                                return Some(format!(
+                                    "Not eligible for {} in bucket {}",
+                                    definition.name, bucket_index
+                                ));
                             }
                         }
+                        None => {
+                            return Some(format!(
+                                "Invalid ancillary {} in bucket {}",
+                                ancillary, bucket_index
+                            ))
+                        }
                     }
                 }
-                None => {
-                    eprintln!("Empty selections value.");
-                }
-            };
+            }
         }
+        None
+    }
+
+    //Finalized rounds are frozen. Every other round is recomputed in order, so each sees the final designations of the rounds before it.
+    fn determine_designations_from_current_selections(&mut self) {
+        self.determine_designations(true);
+    }
 
-        //Clear all designations
-        for state in &mut self.bucket_states {
-            for state in state.get_states_mut() {
+    //Logs every pick's result unless quiet
+    fn determine_designations(&mut self, log: bool) {
+        for round in 0..self.basis.get_selection_rounds().len() {
+            if self.is_finalized(&round) {
+                continue;
+            }
+
+            //Clear all designations
+            for state in &mut self.bucket_states {
+                let state = state.get_state_mut(&round);
                 state.designations.clear();
                 state.ancillary_designations.clear();
+                state.waitlist.clear();
+                state.apply_overrides();
+            }
+
+            if log {
+                println!("Determining designations for round {}", round);
             }
+            self.determine_designations_for_round(round, log);
         }
 
-        //Sort selections to attempt in order of highest rank to lowest rank
-        selections_to_attempt
-            .sort_by(|a: &SelectionInstance, b: &SelectionInstance| a.rank.cmp(&b.rank));
-        for mut selection_instance in selections_to_attempt {
-            println!(
-                "Selection rank: {}, participant {}, bucket {}",
-                selection_instance.rank,
-                selection_instance.participant,
-                selection_instance.selection.bucket_index
-            );
-            let result = self.attempt_selection(
-                &selection_instance.round,
-                &selection_instance.participant,
-                &selection_instance.selection,
-            );
+        //Caller must save state so selection results persist.
+    }
 
-            println!("Selection result: {:?}", result);
-            match self.selections.get_mut(&selection_instance.round) {
-                Some(selection_map) => match selection_map.get_mut(&selection_instance.participant)
-                {
-                    Some(selections) => {
-                        match selections.get_mut(selection_instance.selection_index) {
-                            Some(selection) => match selection {
-                                Some(selection) => {
-                                    selection.state = Some(result);
-                                }
-                                None => {
-                                    eprintln!("Malformed selection instance.");
-                                }
-                            },
-                            None => {
-                                eprintln!("Malformed selection instance.");
+    //Deferred acceptance. Each pick proposes its current option, best rank first, and each bucket keeps its best ranked proposals.
+    //A pick whose option is rejected, or later displaced by a better rank, moves on to its next option.
+    fn determine_designations_for_round(&mut self, round: RoundIndex, log: bool) {
+        struct PendingPick {
+            participant: ParticipantIndex,
+            pick_index: usize,
+            option_index: usize,
+        }
+
+        let mut selections_this_round = match self.selections.get(&round) {
+            Some(participant_selections_map) => participant_selections_map.clone(),
+            None => {
+                eprintln!("Empty selections value.");
+                return;
+            }
+        };
+
+        let mut pick_count: usize = 0;
+        for selections in selections_this_round.values_mut() {
+            pick_count = pick_count.max(selections.len());
+            for selection in selections.iter_mut().flatten() {
+                selection.clear_results();
+            }
+        }
+
+        let strategy = self.basis.get_allocation().strategy();
+
+        //Which pick holds each participant's designation in a bucket, so a displaced holder can fall back
+        let mut holders: BTreeMap<(BucketIndex, ParticipantIndex), usize> = BTreeMap::new();
+        let mut waitlisted: BTreeMap<BucketIndex, Vec<WaitlistEntry>> = BTreeMap::new();
+
+        //Everyone's first pick is settled before anyone's second pick, and so on.
+        //Designations settled by an earlier pick are locked, so a later pick can't displace them.
+        for pick_index in 0..pick_count {
+            let locked: Vec<Designations> = self
+                .bucket_states
+                .iter()
+                .map(|bucket_state| bucket_state.get_state(&round).designations.clone())
+                .collect();
+
+            let mut pending: Vec<PendingPick> = Vec::new();
+            for (participant, selections) in selections_this_round.iter() {
+                match selections.get(pick_index) {
+                    Some(Some(_)) => pending.push(PendingPick {
+                        participant: *participant,
+                        pick_index: pick_index,
+                        option_index: 0,
+                    }),
+                    _ => { //Do Nothing
+                    }
+                }
+            }
+
+            while !pending.is_empty() {
+                let next = (0..pending.len())
+                    .min_by_key(|n| {
+                        let pick = &pending[*n];
+                        let (bucket_index, _) = selections_this_round[&pick.participant]
+                            [pick.pick_index]
+                            .as_ref()
+                            .expect("Pending picks should exist.")
+                            .get_option(pick.option_index);
+                        let priority = strategy.priority(
+                            self.bucket_states
+                                .get(bucket_index)
+                                .expect("Bucket should exist.")
+                                .get_state(&round),
+                            &pick.participant,
+                        );
+                        (priority, pick.pick_index)
+                    })
+                    .expect("Should exist.");
+                let pick = pending.swap_remove(next);
+
+                let selection = selections_this_round
+                    .get_mut(&pick.participant)
+                    .expect("Participant should exist.")
+                    .get_mut(pick.pick_index)
+                    .expect("Pick should exist.")
+                    .as_mut()
+                    .expect("Pending picks should exist.");
+                let (bucket_index, ancillaries) = selection.get_option(pick.option_index);
+                let ancillaries = ancillaries.clone();
+
+                if log {
+                    println!(
+                        "Participant {}, pick {}, option {}, bucket {}",
+                        pick.participant, pick.pick_index, pick.option_index, bucket_index
+                    );
+                }
+                let conflicts =
+                    self.calendar_conflicts(&round, &pick.participant, bucket_index, &locked);
+                let (result, displaced) = match conflicts.is_empty() {
+                    true => self.attempt_selection(
+                        &round,
+                        &pick.participant,
+                        bucket_index,
+                        &ancillaries,
+                        &locked,
+                    ),
+                    false => (
+                        SelectionResult::RejectedCalendarConflict(conflicts),
+                        Vec::new(),
+                    ),
+                };
+                if log {
+                    println!("Selection result: {:?}", result);
+                }
+                if result.is_outranked() {
+                    waitlisted
+                        .entry(bucket_index)
+                        .or_default()
+                        .push(WaitlistEntry {
+                            participant: pick.participant,
+                            pick_index: pick.pick_index,
+                            option_index: pick.option_index,
+                        });
+                }
+
+                if result == SelectionResult::Confirmed {
+                    holders.insert((bucket_index, pick.participant), pick.pick_index);
+                } else if pick.option_index + 1 < selection.option_count() {
+                    pending.push(PendingPick {
+                        participant: pick.participant,
+                        pick_index: pick.pick_index,
+                        option_index: pick.option_index + 1,
+                    });
+                }
+                let explanation = self.explain(&round, &pick.participant, bucket_index, &result);
+                selection.set_option_result(pick.option_index, result, explanation);
+
+                for (displaced_bucket, displaced_participant, displaced_result) in displaced {
+                    if log {
+                        println!(
+                            "Participant {} displaced from bucket {}: {:?}",
+                            displaced_participant, displaced_bucket, displaced_result
+                        );
+                    }
+                    match holders.remove(&(displaced_bucket, displaced_participant)) {
+                        Some(displaced_pick_index) => {
+                            let displaced_selection = selections_this_round
+                                .get_mut(&displaced_participant)
+                                .expect("Participant should exist.")
+                                .get_mut(displaced_pick_index)
+                                .expect("Pick should exist.")
+                                .as_mut()
+                                .expect("Holding picks should exist.");
+                            let displaced_option = displaced_selection
+                                .granted_option
+                                .expect("Holding picks should have a granted option.");
+                            if displaced_result.is_outranked() {
+                                waitlisted.entry(displaced_bucket).or_default().push(
+                                    WaitlistEntry {
+                                        participant: displaced_participant,
+                                        pick_index: displaced_pick_index,
+                                        option_index: displaced_option,
+                                    },
+                                );
+                            }
+                            let explanation = self.explain(
+                                &round,
+                                &displaced_participant,
+                                displaced_bucket,
+                                &displaced_result,
+                            );
+                            displaced_selection.set_option_result(
+                                displaced_option,
+                                displaced_result,
+                                explanation,
+                            );
+
+                            if displaced_option + 1 < displaced_selection.option_count() {
+                                pending.push(PendingPick {
+                                    participant: displaced_participant,
+                                    pick_index: displaced_pick_index,
+                                    option_index: displaced_option + 1,
+                                });
                             }
                         }
+                        None => {
+                            eprintln!("Malformed selection instance.");
+                        }
                     }
-                    None => {
-                        eprintln!("Malformed selection instance.");
-                    }
-                },
-                None => {
-                    eprintln!("Malformed selection instance.");
                 }
             }
         }
 
-        //Caller must save state so selection results persist.
+        self.selections.set_round(round, selections_this_round);
+
+        //Anyone outranked who didn't end up in the bucket anyway, once each, best rank first
+        for (bucket_index, mut entries) in waitlisted {
+            let round_state = self
+                .bucket_states
+                .get_mut(bucket_index)
+                .expect("Bucket should exist.")
+                .get_state_mut(&round);
+            entries.sort_by_key(|entry| (entry.pick_index, entry.option_index));
+            let mut waitlist: Vec<(_, WaitlistEntry)> = Vec::new();
+            for entry in entries {
+                if round_state.designations.contains(&entry.participant)
+                    || waitlist
+                        .iter()
+                        .any(|(_, listed)| listed.participant == entry.participant)
+                {
+                    continue;
+                }
+                waitlist.push((strategy.priority(round_state, &entry.participant), entry));
+            }
+            waitlist.sort_by_key(|(priority, _)| *priority);
+            round_state.waitlist = waitlist.into_iter().map(|(_, entry)| entry).collect();
+        }
     }
 
     fn save_state(
         &mut self,
         id: String,
-        conn: &mut PgConnection,
+        store: &mut dyn DivisionStore,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        PersistentDivision::update(conn, id, self)
+        store.update_state(&id, self)
     }
 
-    fn generate_ranks(&mut self) {
-        let mut initial_available_ranks: BTreeSet<usize> = BTreeSet::new();
+    fn generate_ranks(&mut self, log: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let lottery = match &self.lottery {
+            Some(lottery) => lottery,
+            None => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "No lottery record to generate ranks from.",
+                )))
+            }
+        };
 
-        for n in 1..self.basis.get_participant_definitions().len() + 1 {
-            initial_available_ranks.insert(n);
+        if log {
+            println!(
+                "Drawing ranks for {} rounds with {:?}.",
+                self.basis.get_selection_rounds().len(),
+                lottery.get_algorithm()
+            );
         }
+        let ranks = self.basis.get_allocation().strategy().draw_ranks(
+            lottery,
+            self.basis.get_participant_definitions().len(),
+            self.basis.get_bucket_definitions().len(),
+            self.basis.get_selection_rounds().len(),
+        )?;
 
-        let mut rng: ThreadRng = thread_rng();
+        for (bucket, bucket_ranks) in self.bucket_states.iter_mut().zip(ranks) {
+            for (round, round_ranks) in bucket_ranks.into_iter().enumerate() {
+                bucket.get_state_mut(&round).ranks = Some(round_ranks);
+            }
+        }
 
-        println!(
-            "Iterating through {} rounds.",
-            self.basis.get_selection_rounds().len()
-        );
-        for round in 0..self.basis.get_selection_rounds().len() {
-            println!("Round {}", round);
-            for bucket in self.bucket_states.iter_mut() {
-                let mut bucket_state_this_round: BTreeMap<ParticipantIndex, usize> =
-                    BTreeMap::new();
-
-                for participant in 0..self.basis.get_participant_definitions().len() {
-                    /*
-                    println!("");
-                    println!(
-                        "Participant {} round {} bucket {}",
-                        participant.to_string(),
-                        round,
-                        bucket_name
-                    );
+        Ok(())
+    }
 
-                    println!(
-                        "Ranks already used for this bucket and round are {:?}",
-                        (&bucket_state_this_round).values()
-                    );
-                    */
+    pub fn verify_ranks(&self) -> Result<RankVerification, Box<dyn std::error::Error>> {
+        let lottery =
+            match &self.lottery {
+                Some(lottery) => lottery,
+                None => return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "This division predates seeded lotteries, so its ranks can't be re-derived.",
+                ))),
+            };
 
-                    let mut available_ranks: BTreeSet<usize> = BTreeSet::new();
-                    for r in initial_available_ranks.iter() {
-                        available_ranks.insert(r.clone());
-                    }
-                    for r in (&bucket_state_this_round).values() {
-                        available_ranks.remove(r);
+        let seed = match lottery.get_seed() {
+            Some(seed) => seed.to_string(),
+            None => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Lottery seed has been censored.",
+                )))
+            }
+        };
+
+        //Replay the original draw at the size the division started with, then the draws of each addition
+        let added = |added: fn(&BasisAmendment) -> bool| {
+            self.amendments
+                .iter()
+                .filter(|amendment| added(&amendment.change))
+                .count()
+        };
+        let mut participant_count = self.basis.get_participant_definitions().len()
+            - added(|change| matches!(change, BasisAmendment::AddParticipant(_)));
+        let round_count = self.basis.get_selection_rounds().len();
+        let mut expected = self.basis.get_allocation().strategy().draw_ranks(
+            lottery,
+            participant_count,
+            self.basis.get_bucket_definitions().len()
+                - added(|change| matches!(change, BasisAmendment::AddBucket(_))),
+            round_count,
+        )?;
+        for amendment in self.amendments.iter() {
+            if let BasisAmendment::AddParticipant(_) = amendment.change {
+                participant_count += 1;
+            }
+            amendment.extend_ranks(
+                &mut expected,
+                self.basis.get_allocation(),
+                participant_count,
+                round_count,
+            )?;
+        }
+
+        let mut mismatches: Vec<RankMismatch> = Vec::new();
+        for (bucket_index, bucket_ranks) in expected.into_iter().enumerate() {
+            for (round_index, round_ranks) in bucket_ranks.into_iter().enumerate() {
+                let stored = &self
+                    .bucket_states
+                    .get(bucket_index)
+                    .expect("Bucket should exist.")
+                    .get_state(&round_index)
+                    .ranks;
+                if stored.as_ref() != Some(&round_ranks) {
+                    mismatches.push(RankMismatch {
+                        bucket_index,
+                        round_index,
+                    });
+                }
+            }
+        }
+
+        Ok(RankVerification {
+            algorithm: *lottery.get_algorithm(),
+            seed,
+            weights: lottery.get_weights().clone(),
+            matches: mismatches.is_empty(),
+            mismatches,
+        })
+    }
+
+    //Buckets the participant is designated to in any round, with the ancillaries held alongside each
+    pub fn get_holdings(
+        &self,
+        participant: &ParticipantIndex,
+    ) -> BTreeMap<BucketIndex, BTreeSet<AncillaryIndex>> {
+        self.holdings_in_rounds(participant, |_| true)
+    }
+
+    //Like get_holdings, but only from finalized rounds, whose designations no longer change with submissions
+    pub fn get_finalized_holdings(
+        &self,
+        participant: &ParticipantIndex,
+    ) -> BTreeMap<BucketIndex, BTreeSet<AncillaryIndex>> {
+        self.holdings_in_rounds(participant, |round| self.is_finalized(round))
+    }
+
+    fn holdings_in_rounds<F>(
+        &self,
+        participant: &ParticipantIndex,
+        include: F,
+    ) -> BTreeMap<BucketIndex, BTreeSet<AncillaryIndex>>
+    where
+        F: Fn(&RoundIndex) -> bool,
+    {
+        let mut retval: BTreeMap<BucketIndex, BTreeSet<AncillaryIndex>> = BTreeMap::new();
+        for (bucket_index, bucket_state) in self.bucket_states.iter().enumerate() {
+            for (round, round_state) in bucket_state.get_states().iter().enumerate() {
+                if include(&round) && round_state.designations.contains(participant) {
+                    let ancillaries = retval.entry(bucket_index).or_default();
+                    for (ancillary, designees) in round_state.ancillary_designations.iter() {
+                        if designees.contains(participant) {
+                            ancillaries.insert(*ancillary);
+                        }
                     }
-                    let mut available_ranks_as_vec: Vec<usize> = Vec::new();
-                    for r in available_ranks {
-                        available_ranks_as_vec.push(r.to_owned());
+                }
+            }
+        }
+        retval
+    }
+
+    pub(crate) fn censor_lottery(&mut self) {
+        if let Some(lottery) = self.lottery.as_mut() {
+            lottery.censor();
+        }
+        for amendment in self.amendments.iter_mut() {
+            amendment.censor();
+        }
+    }
+
+    pub(crate) fn pretty_print(&self) {
+        let serialized = serde_json::to_string_pretty(self).expect("Should serialize.");
+        println!("{}", serialized);
+    }
+
+    fn slots_available_this_round(&self, bucket_index: &usize, current_round: &usize) -> usize {
+        let mut used_slots: usize = 0;
+
+        for previous_round in 0..*current_round {
+            used_slots += self
+                .bucket_states
+                .get(*bucket_index)
+                .expect("Bucket should exist.")
+                .get_state(&previous_round)
+                .designations
+                .len() as usize;
+        }
+
+        let available_slots = self
+            .basis
+            .get_bucket_definitions()
+            .get(*bucket_index)
+            .expect("Bucket should exist.")
+            .available_slots;
+
+        available_slots.saturating_sub(used_slots)
+    }
+
+    //Buckets the participant already holds, from earlier rounds or earlier picks this round, whose dates overlap this bucket.
+    fn calendar_conflicts(
+        &self,
+        round: &RoundIndex,
+        participant: &ParticipantIndex,
+        bucket_index: BucketIndex,
+        locked: &Vec<Designations>,
+    ) -> Vec<BucketIndex> {
+        let bucket_definitions = self.basis.get_bucket_definitions();
+        let requested = bucket_definitions
+            .get(bucket_index)
+            .expect("Bucket should exist.");
+
+        let mut retval: Vec<BucketIndex> = Vec::new();
+        for (held_index, held) in bucket_definitions.iter().enumerate() {
+            if held_index == bucket_index || !held.overlaps(requested) {
+                continue;
+            }
+
+            let bucket_state = self
+                .bucket_states
+                .get(held_index)
+                .expect("Bucket should exist.");
+            let held_earlier = (0..*round).any(|previous_round| {
+                bucket_state
+                    .get_state(&previous_round)
+                    .designations
+                    .contains(participant)
+            });
+            if held_earlier || locked[held_index].contains(participant) {
+                retval.push(held_index);
+            }
+        }
+        retval
+    }
+
+    //Bucket weight held from earlier rounds or earlier picks this round
+    fn quota_used(
+        &self,
+        round: &RoundIndex,
+        participant: &ParticipantIndex,
+        locked: &Vec<Designations>,
+    ) -> u32 {
+        let mut retval: u32 = 0;
+        for (bucket_index, bucket) in self.basis.get_bucket_definitions().iter().enumerate() {
+            let bucket_state = self
+                .bucket_states
+                .get(bucket_index)
+                .expect("Bucket should exist.");
+            let held_earlier = (0..*round).any(|previous_round| {
+                bucket_state
+                    .get_state(&previous_round)
+                    .designations
+                    .contains(participant)
+            });
+            if held_earlier || locked[bucket_index].contains(participant) {
+                retval += bucket.quota_weight;
+            }
+        }
+        retval
+    }
+
+    //Bucket weight the participant currently holds over every round, counted against their max_buckets
+    pub fn get_quota_used(&self, participant: &ParticipantIndex) -> u32 {
+        self.get_holdings(participant)
+            .keys()
+            .map(|bucket_index| self.basis.get_bucket_definitions()[*bucket_index].quota_weight)
+            .sum()
+    }
+
+    //Returns the result for this participant and any previous designees this displaced, with the bucket each lost and why.
+    //Locked designees were settled by an earlier pick, so they keep their slot and ancillaries regardless of rank.
+    pub fn attempt_selection(
+        &mut self,
+        round: &usize,
+        participant: &ParticipantIndex,
+        bucket_index: BucketIndex,
+        ancillaries: &BTreeSet<AncillaryIndex>,
+        locked: &Vec<Designations>,
+    ) -> (
+        SelectionResult,
+        Vec<(BucketIndex, ParticipantIndex, SelectionResult)>,
+    ) {
+        if self.basis.get_bucket_definitions()[bucket_index].removed {
+            return (SelectionResult::RejectedBucketRemoved, Vec::new());
+        }
+        if locked[bucket_index].contains(participant) {
+            return (SelectionResult::RejectedAlreadyDesignated, Vec::new());
+        }
+        if self.bucket_states[bucket_index]
+            .get_state(round)
+            .released
+            .contains_key(participant)
+        {
+            return (SelectionResult::Released, Vec::new());
+        }
+        if let Some(max_buckets) =
+            self.basis.get_participant_definitions()[*participant].get_max_buckets()
+        {
+            let requested = self.basis.get_bucket_definitions()[bucket_index].quota_weight;
+            if self.quota_used(round, participant, locked) + requested > max_buckets {
+                return (SelectionResult::RejectedQuotaReached, Vec::new());
+            }
+        }
+
+        //Check ancillary designations first. If this participant can't get any of them, reject the selection
+        let mut unavailable_ancillaries: Vec<usize> = Vec::new();
+        let mut ancillary_losers: BTreeMap<(BucketIndex, ParticipantIndex), Vec<AncillaryIndex>> =
+            BTreeMap::new();
+        for ancillary in ancillaries {
+            match self.contest_ancillary(round, participant, bucket_index, ancillary, locked) {
+                Some(losers) => {
+                    for (loser_bucket, loser, lost) in losers {
+                        ancillary_losers
+                            .entry((loser_bucket, loser))
+                            .or_default()
+                            .push(lost);
                     }
-                    /*
-                    println!(
-                        "This leaves the following available ranks for the randomizer {:?}",
-                        (&available_ranks_as_vec)
-                    );
-                    */
+                }
+                None => unavailable_ancillaries.push(*ancillary),
+            }
+        }
+        if unavailable_ancillaries.len() > 0 {
+            return (
+                SelectionResult::RejectedAncillaryUnavailable(unavailable_ancillaries),
+                Vec::new(),
+            );
+        }
+
+        let slots_available = self.slots_available_this_round(&bucket_index, round);
+        let slots_contestable = slots_available.saturating_sub(locked[bucket_index].len());
+        if slots_contestable == 0 {
+            return (SelectionResult::RejectedNoSelectionsThisRound, Vec::new());
+        }
+
+        let round_state = self
+            .bucket_states
+            .get(bucket_index)
+            .expect("Bucket should exist.")
+            .get_state(&round);
+
+        let mut candidates: BTreeSet<ParticipantIndex> = round_state
+            .designations
+            .iter()
+            .filter(|designee| {
+                !locked[bucket_index].contains(designee)
+                    && !ancillary_losers.contains_key(&(bucket_index, **designee))
+            })
+            .cloned()
+            .collect();
+        candidates.insert(*participant);
+
+        let mut winners = self.basis.get_allocation().strategy().get_winners(
+            round_state,
+            &candidates,
+            slots_contestable,
+        );
+        if !winners.contains(participant) {
+            return (SelectionResult::RejectedOutranked, Vec::new());
+        }
+        winners.extend(locked[bucket_index].iter().cloned());
+
+        //Release everything held by displaced designees, including those in other buckets sharing an ancillary
+        let mut displaced: Vec<(BucketIndex, ParticipantIndex, SelectionResult)> = Vec::new();
+        for ((loser_bucket, loser), lost) in ancillary_losers {
+            displaced.push((
+                loser_bucket,
+                loser,
+                SelectionResult::RejectedAncillaryUnavailable(lost),
+            ));
+        }
+        for designee in round_state.designations.iter() {
+            if !winners.contains(designee)
+                && !displaced
+                    .iter()
+                    .any(|(b, p, _)| *b == bucket_index && p == designee)
+            {
+                displaced.push((bucket_index, *designee, SelectionResult::RejectedOutranked));
+            }
+        }
+        for (displaced_bucket, displaced_participant, _) in displaced.iter() {
+            self.bucket_states
+                .get_mut(*displaced_bucket)
+                .expect("Bucket should exist.")
+                .get_state_mut(&round)
+                .release(displaced_participant);
+        }
+
+        let round_state = self
+            .bucket_states
+            .get_mut(bucket_index)
+            .expect("Bucket should exist.")
+            .get_state_mut(&round);
+        round_state.designations = winners;
+        for ancillary in ancillaries {
+            round_state
+                .ancillary_designations
+                .entry(*ancillary)
+                .or_default()
+                .insert(*participant);
+        }
+
+        (SelectionResult::Confirmed, displaced)
+    }
+
+    //Why the result came about, from the designations as they stand right after it was decided
+    fn explain(
+        &self,
+        round: &RoundIndex,
+        participant: &ParticipantIndex,
+        bucket_index: BucketIndex,
+        result: &SelectionResult,
+    ) -> Explanation {
+        let round_state = self.bucket_states[bucket_index].get_state(round);
+        let rival = |holder: &ParticipantIndex| Rival {
+            participant: *holder,
+            rank: round_state
+                .ranks
+                .as_ref()
+                .and_then(|ranks| ranks.get(holder))
+                .cloned(),
+        };
+        match result {
+            SelectionResult::Confirmed => Explanation::Granted {
+                rank: rival(participant).rank,
+            },
+            SelectionResult::RejectedOutranked => {
+                let strategy = self.basis.get_allocation().strategy();
+                let priority = strategy.priority(round_state, participant);
+                let mut holders: Vec<&ParticipantIndex> = round_state
+                    .designations
+                    .iter()
+                    .filter(|holder| strategy.priority(round_state, holder) < priority)
+                    .collect();
+                holders.sort_by_key(|holder| strategy.priority(round_state, holder));
+                Explanation::Outranked {
+                    rank: rival(participant).rank,
+                    holders: holders.into_iter().map(rival).collect(),
+                }
+            }
+            SelectionResult::RejectedNoSelectionsThisRound => {
+                let slots = self.basis.get_bucket_definitions()[bucket_index].available_slots;
+                Explanation::SlotsUsed {
+                    slots: slots,
+                    earlier_rounds: slots - self.slots_available_this_round(&bucket_index, round),
+                    holders: round_state.designations.iter().map(rival).collect(),
+                }
+            }
+            SelectionResult::RejectedAncillaryUnavailable(ancillaries) => {
+                Explanation::AncillaryUnavailable(
+                    ancillaries
+                        .iter()
+                        .filter_map(|ancillary| {
+                            self.explain_ancillary(round, participant, bucket_index, ancillary)
+                        })
+                        .collect(),
+                )
+            }
+            SelectionResult::RejectedAlreadyDesignated => Explanation::AlreadyDesignated,
+            SelectionResult::RejectedCalendarConflict(conflicts) => {
+                Explanation::CalendarConflict(conflicts.clone())
+            }
+            SelectionResult::RejectedQuotaReached => Explanation::QuotaReached {
+                used: self.get_quota_used(participant),
+                requested: self.basis.get_bucket_definitions()[bucket_index].quota_weight,
+                max_buckets: self.basis.get_participant_definitions()[*participant]
+                    .get_max_buckets()
+                    .unwrap_or(0),
+            },
+            SelectionResult::Released => Explanation::Released(
+                round_state
+                    .released
+                    .get(participant)
+                    .expect("Released participants should have a release.")
+                    .clone(),
+            ),
+            SelectionResult::RejectedBucketRemoved => Explanation::BucketRemoved,
+            SelectionResult::SwappedAway => {
+                let given = Holding::create(*round, bucket_index);
+                Explanation::SwappedAway {
+                    swap_index: self
+                        .swaps
+                        .iter()
+                        .rposition(|swap| {
+                            swap.status == SwapStatus::Accepted
+                                && ((swap.proposer == *participant
+                                    && swap.proposer_holding == given)
+                                    || (swap.recipient == *participant
+                                        && swap.recipient_holding == given))
+                        })
+                        .expect("Swapped picks should have an accepted swap."),
+                }
+            }
+        }
+    }
+
+    //Who holds the ancillary's capacity, through any bucket in its pool, in this round and every earlier one
+    fn explain_ancillary(
+        &self,
+        round: &RoundIndex,
+        participant: &ParticipantIndex,
+        bucket_index: BucketIndex,
+        ancillary: &AncillaryIndex,
+    ) -> Option<AncillaryShortfall> {
+        let (definition, pool) = self.basis.resolve_ancillary(&bucket_index, ancillary)?;
+        let mut holders: Vec<AncillaryHolder> = Vec::new();
+        for (member_bucket, member_ancillary) in self.basis.get_pool_members(&pool) {
+            for holder_round in 0..*round + 1 {
+                let round_state = self.bucket_states[member_bucket].get_state(&holder_round);
+                for holder in round_state
+                    .ancillary_designations
+                    .get(&member_ancillary)
+                    .into_iter()
+                    .flatten()
+                {
+                    holders.push(AncillaryHolder {
+                        participant: *holder,
+                        bucket_index: member_bucket,
+                        round: holder_round,
+                        rank: match holder_round == *round {
+                            true => round_state
+                                .ranks
+                                .as_ref()
+                                .and_then(|ranks| ranks.get(holder))
+                                .cloned(),
+                            false => None,
+                        },
+                    });
+                }
+            }
+        }
+        holders.sort();
+
+        Some(AncillaryShortfall {
+            ancillary: *ancillary,
+            capacity: definition.capacity,
+            eligible: definition.is_eligible(participant),
+            holders: holders,
+        })
+    }
+
+    //Rank details in rounds whose ranks are hidden from participants
+    pub(crate) fn censor_explanations(&mut self, round: &RoundIndex) {
+        if let Some(round_selections) = self.selections.get_mut(round) {
+            for selection in round_selections.values_mut().flatten().flatten() {
+                for explanation in selection.explanations.values_mut() {
+                    explanation.censor_ranks();
+                }
+            }
+        }
+    }
+
+    //None if the participant can't have the ancillary. Otherwise the holders they would bump, with the bucket and ancillary index each held it by.
+    //Holders from earlier rounds and locked holders keep their place. Everyone else in the pool is ordered by rank in the bucket they hold it through.
+    fn contest_ancillary(
+        &self,
+        round: &RoundIndex,
+        participant: &ParticipantIndex,
+        bucket_index: BucketIndex,
+        ancillary: &AncillaryIndex,
+        locked: &Vec<Designations>,
+    ) -> Option<Vec<(BucketIndex, ParticipantIndex, AncillaryIndex)>> {
+        let (definition, pool) = self.basis.resolve_ancillary(&bucket_index, ancillary)?;
+        if !definition.is_eligible(participant) {
+            return None;
+        }
+
+        let mut settled: usize = 0;
+        let strategy = self.basis.get_allocation().strategy();
+        let mut holders: Vec<((usize, ParticipantIndex), BucketIndex, AncillaryIndex)> = Vec::new();
+        for (member_bucket, member_ancillary) in self.basis.get_pool_members(&pool) {
+            let bucket_state = self
+                .bucket_states
+                .get(member_bucket)
+                .expect("Bucket should exist.");
+            for previous_round in 0..*round {
+                settled += bucket_state
+                    .get_state(&previous_round)
+                    .ancillary_designations
+                    .get(&member_ancillary)
+                    .map_or(0, |previous_holders| previous_holders.len());
+            }
+
+            let round_state = bucket_state.get_state(round);
+            for holder in round_state
+                .ancillary_designations
+                .get(&member_ancillary)
+                .into_iter()
+                .flatten()
+            {
+                if locked[member_bucket].contains(holder) {
+                    settled += 1;
+                } else if !(member_bucket == bucket_index && holder == participant) {
+                    holders.push((
+                        strategy.priority(round_state, holder),
+                        member_bucket,
+                        member_ancillary,
+                    ));
+                }
+            }
+        }
+
+        let contestable = definition.capacity.saturating_sub(settled);
+        let priority = strategy.priority(
+            self.bucket_states
+                .get(bucket_index)
+                .expect("Bucket should exist.")
+                .get_state(round),
+            participant,
+        );
+        holders.sort();
+        let better_holders = holders
+            .iter()
+            .filter(|(holder_priority, _, _)| *holder_priority < priority)
+            .count();
+        if better_holders >= contestable {
+            return None;
+        }
+
+        //The participant takes one of the contestable places, so only the best contestable - 1 holders keep theirs
+        Some(
+            holders
+                .into_iter()
+                .skip(contestable - 1)
+                .map(|((_, holder), holder_bucket, holder_ancillary)| {
+                    (holder_bucket, holder, holder_ancillary)
+                })
+                .collect(),
+        )
+    }
+
+    pub fn set_open_round(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+        round_index: Option<usize>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        BlockDivisionState::modify_in_store(
+            store,
+            state_id,
+            DivisionEvent::RoundOpened(round_index),
+            |state| state.open_round(round_index),
+        )
+    }
+
+    pub fn close_round(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+        round_index: usize,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let event = DivisionEvent::RoundClosed(round_index);
+        let entries =
+            BlockDivisionState::modify_in_store(store, state_id.clone(), event, |state| {
+                state.finalize_round(round_index)?;
+                match state.is_complete() {
+                    true => Ok(Some(ledger::entries_from_state(&state_id, state))),
+                    false => Ok(None),
+                }
+            })?;
+
+        //Closing the last round completes the division, so its outcomes go in the ledger
+        match entries {
+            Some(entries) => store.record_ledger(&state_id, &entries),
+            None => Ok(()),
+        }
+    }
+
+    //Carries out every scheduled open and close due by now, earliest round first, through the same paths as an admin would.
+    pub fn apply_schedule(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ScheduledChange>, Box<dyn std::error::Error>> {
+        let mut applied = Vec::new();
+        loop {
+            //Reloaded every time, since opening a round finalizes the ones before it
+            let change = match store.get_state(&state_id)? {
+                Some(state) => state.next_scheduled_change(now),
+                None => None,
+            };
+            let change = match change {
+                Some(change) => change,
+                None => return Ok(applied),
+            };
+            //Marked in the same transaction, so a change is never carried out without being marked and then repeated
+            store.in_transaction(&mut |store| {
+                match change.event {
+                    ScheduleEvent::Open => BlockDivisionState::set_open_round(
+                        store,
+                        state_id.clone(),
+                        Some(change.round),
+                    )?,
+                    ScheduleEvent::Close => {
+                        BlockDivisionState::close_round(store, state_id.clone(), change.round)?
+                    }
+                }
+                let event = DivisionEvent::ScheduleApplied(change);
+                BlockDivisionState::modify_in_store(store, state_id.clone(), event, |state| {
+                    state.schedule_applied.insert(change);
+                    Ok(())
+                })
+            })?;
+            applied.push(change);
+        }
+    }
+
+    //Takes a participant out of a bucket, either by their own request or an admin's. Returns who took their place.
+    pub fn withdraw(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+        round: RoundIndex,
+        bucket_index: BucketIndex,
+        participant: ParticipantIndex,
+        release: Release,
+    ) -> Result<Vec<Promotion>, Box<dyn std::error::Error>> {
+        let event = DivisionEvent::Withdrawn {
+            round: round,
+            bucket_index: bucket_index,
+            participant: participant,
+            release: release.clone(),
+        };
+        BlockDivisionState::modify_in_store(store, state_id, event, |state| {
+            state.release_designation(round, bucket_index, participant, release)
+        })
+    }
+
+    pub fn propose_swap(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+        proposer: ParticipantIndex,
+        proposer_holding: Holding,
+        recipient: ParticipantIndex,
+        recipient_holding: Holding,
+        at: DateTime<Utc>,
+    ) -> Result<SwapIndex, Box<dyn std::error::Error>> {
+        let event = DivisionEvent::SwapProposed {
+            proposer: proposer,
+            proposer_holding: proposer_holding,
+            recipient: recipient,
+            recipient_holding: recipient_holding,
+            at: at,
+        };
+        BlockDivisionState::modify_in_store(store, state_id, event, |state| {
+            state.add_swap_proposal(proposer, proposer_holding, recipient, recipient_holding, at)
+        })
+    }
+
+    //The recipient accepts or declines. The proposer may only cancel.
+    pub fn respond_to_swap(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+        participant: ParticipantIndex,
+        swap_index: SwapIndex,
+        accept: bool,
+        at: DateTime<Utc>,
+    ) -> Result<SwapStatus, Box<dyn std::error::Error>> {
+        let event = DivisionEvent::SwapResolved {
+            participant: participant,
+            swap_index: swap_index,
+            accept: accept,
+            at: at,
+        };
+        BlockDivisionState::modify_in_store(store, state_id, event, |state| {
+            state.resolve_swap(participant, swap_index, accept, at)
+        })
+    }
+
+    pub fn set_withdrawal_policy(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+        policy: WithdrawalPolicy,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let event = DivisionEvent::WithdrawalPolicySet(policy);
+        BlockDivisionState::modify_in_store(store, state_id, event, |state| {
+            state.withdrawal_policy = policy;
+            Ok(())
+        })
+    }
+
+    //Places the participant in the bucket regardless of rank, slots or quota
+    pub fn override_designation(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+        round: RoundIndex,
+        bucket_index: BucketIndex,
+        participant: ParticipantIndex,
+        ancillaries: BTreeSet<AncillaryIndex>,
+        at: DateTime<Utc>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let event = DivisionEvent::OverrideApplied {
+            round: round,
+            bucket_index: bucket_index,
+            participant: participant,
+            ancillaries: ancillaries.clone(),
+            at: at,
+        };
+        BlockDivisionState::modify_in_store(store, state_id, event, |state| {
+            state.assign_override(round, bucket_index, participant, ancillaries, at)
+        })
+    }
+
+    //Returns who took the freed place
+    pub fn remove_override(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+        round: RoundIndex,
+        bucket_index: BucketIndex,
+        participant: ParticipantIndex,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<Promotion>, Box<dyn std::error::Error>> {
+        let event = DivisionEvent::OverrideRemoved {
+            round: round,
+            bucket_index: bucket_index,
+            participant: participant,
+            at: at,
+        };
+        BlockDivisionState::modify_in_store(store, state_id, event, |state| {
+            state.clear_override(round, bucket_index, participant, at)
+        })
+    }
+
+    //Changes the basis while keeping ranks and selections. Returns anyone promoted into a finalized round as a result.
+    pub fn amend(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+        change: BasisAmendment,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<Promotion>, Box<dyn std::error::Error>> {
+        //Drawn before the change so the event records the lottery
+        let amendment = match store.get_state(&state_id)? {
+            Some(state) => {
+                let ledger = match state.get_basis().is_ledger_weighted() {
+                    true => store.get_ledger(None)?,
+                    false => Ledger::new(),
+                };
+                state.draw_amendment(change, at, &ledger)
+            }
+            None => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("No state with id {}", state_id),
+                )))
+            }
+        };
+        let event = DivisionEvent::Amended(amendment.clone());
+        BlockDivisionState::modify_in_store(store, state_id, event, |state| {
+            state.amend_basis(amendment)
+        })
+    }
+
+    //Records a completed division in the ledger, for divisions completed before the ledger existed
+    pub fn record_in_ledger(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match store.get_state(&state_id)? {
+            Some(state) if state.is_complete() => {
+                store.record_ledger(&state_id, &ledger::entries_from_state(&state_id, &state))
+            }
+            Some(_) => Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} still has open rounds.", state_id),
+            ))),
+            None => Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No state with id {}", state_id),
+            ))),
+        }
+    }
+
+    //The division as it was at the given time, rebuilt from its recorded events
+    pub fn state_at(
+        store: &mut dyn DivisionStore,
+        state_id: &str,
+        at: &DateTime<Utc>,
+    ) -> Result<BlockDivisionState, Box<dyn std::error::Error>> {
+        match history::replay(&store.get_events(state_id)?, at)? {
+            Some(state) => Ok(state),
+            None => Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} has no recorded history as of {}.", state_id, at),
+            ))),
+        }
+    }
+
+    //Loads the state, applies the modification, and saves only if it succeeded.
+    //The event describes the modification, which replaying it must repeat exactly.
+    fn modify_in_store<T, F>(
+        store: &mut dyn DivisionStore,
+        state_id: String,
+        event: DivisionEvent,
+        modification: F,
+    ) -> Result<T, Box<dyn std::error::Error>>
+    where
+        F: FnOnce(&mut BlockDivisionState) -> Result<T, Box<dyn std::error::Error>>,
+    {
+        match store.get_state(&state_id) {
+            Ok(Some(mut state)) => {
+                //Divisions from before events were recorded start their history from here
+                let imported = match store.has_events(&state_id)? {
+                    true => None,
+                    false => Some(DivisionEvent::Imported(Box::new(state.clone()))),
+                };
+                let retval = modification(&mut state)?;
+                let at = Utc::now();
+                store.in_transaction(&mut |store| {
+                    state.save_state(state_id.clone(), store)?;
+                    if let Some(imported) = &imported {
+                        store
+                            .append_event(&state_id, &EventRecord::create(at, imported.clone()))?;
+                    }
+                    store.append_event(&state_id, &EventRecord::create(at, event.clone()))
+                })?;
+                Ok(retval)
+            }
+            Err(e) => Err(e),
+            Ok(None) => Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No state with id {}", state_id),
+            ))),
+        }
+    }
+
+    //The earliest scheduled open or close that is due and hasn't been carried out. Finalized rounds have nothing left to schedule.
+    pub(crate) fn next_scheduled_change(&self, now: DateTime<Utc>) -> Option<ScheduledChange> {
+        for round in 0..self.basis.get_selection_rounds().len() {
+            if self.is_finalized(&round) {
+                continue;
+            }
+            let schedule = self.basis.get_round_schedule(round);
+            for (event, time) in [
+                (ScheduleEvent::Open, schedule.open),
+                (ScheduleEvent::Close, schedule.close),
+            ] {
+                let change = ScheduledChange {
+                    round: round,
+                    event: event,
+                };
+                match time {
+                    Some(time) if time <= now && !self.schedule_applied.contains(&change) => {
+                        return Some(change)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        None
+    }
+
+    //Repeats a recorded change the way the store-level function that recorded it made it
+    pub(crate) fn replay(
+        &mut self,
+        event: &DivisionEvent,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match event {
+            DivisionEvent::SelectionsSubmitted {
+                participant,
+                selections,
+            } => self
+                .apply_selections(*participant, selections.clone())
+                .map(|_| ()),
+            DivisionEvent::RoundOpened(round) => self.open_round(*round),
+            DivisionEvent::RoundClosed(round) => self.finalize_round(*round),
+            DivisionEvent::ScheduleApplied(change) => {
+                self.schedule_applied.insert(*change);
+                Ok(())
+            }
+            DivisionEvent::Withdrawn {
+                round,
+                bucket_index,
+                participant,
+                release,
+            } => self
+                .release_designation(*round, *bucket_index, *participant, release.clone())
+                .map(|_| ()),
+            DivisionEvent::SwapProposed {
+                proposer,
+                proposer_holding,
+                recipient,
+                recipient_holding,
+                at,
+            } => self
+                .add_swap_proposal(
+                    *proposer,
+                    *proposer_holding,
+                    *recipient,
+                    *recipient_holding,
+                    *at,
+                )
+                .map(|_| ()),
+            DivisionEvent::SwapResolved {
+                participant,
+                swap_index,
+                accept,
+                at,
+            } => self
+                .resolve_swap(*participant, *swap_index, *accept, *at)
+                .map(|_| ()),
+            DivisionEvent::WithdrawalPolicySet(policy) => {
+                self.withdrawal_policy = *policy;
+                Ok(())
+            }
+            DivisionEvent::OverrideApplied {
+                round,
+                bucket_index,
+                participant,
+                ancillaries,
+                at,
+            } => self.assign_override(
+                *round,
+                *bucket_index,
+                *participant,
+                ancillaries.clone(),
+                *at,
+            ),
+            DivisionEvent::OverrideRemoved {
+                round,
+                bucket_index,
+                participant,
+                at,
+            } => self
+                .clear_override(*round, *bucket_index, *participant, *at)
+                .map(|_| ()),
+            DivisionEvent::Amended(amendment) => self.amend_basis(amendment.clone()).map(|_| ()),
+            DivisionEvent::Created { .. } | DivisionEvent::Imported(_) | DivisionEvent::Deleted => {
+                Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Only starts or ends a history.",
+                )))
+            }
+        }
+    }
+
+    //Everyone with picks in the open round who hasn't submitted any, once a reminder time before its close has passed.
+    //Only the nearest reminder time counts, so one missed while the server was down isn't sent late.
+    pub(crate) fn due_reminders(&self, now: DateTime<Utc>) -> Vec<Reminder> {
+        let round = match self.current_open_round {
+            Some(round) => round,
+            None => return Vec::new(),
+        };
+        let schedule = self.basis.get_round_schedule(round);
+        let close = match schedule.close {
+            Some(close) if now < close => close,
+            _ => return Vec::new(),
+        };
+        let hours_before_close = match schedule
+            .reminder_hours
+            .iter()
+            .filter(|hours| close - chrono::Duration::hours(**hours as i64) <= now)
+            .min()
+        {
+            Some(hours) => *hours,
+            None => return Vec::new(),
+        };
+
+        let round_selections = match self.selections.get(&round) {
+            Some(round_selections) => round_selections,
+            None => return Vec::new(),
+        };
+        round_selections
+            .iter()
+            .filter(|(participant, selections)| {
+                !self.basis.get_participant_definitions()[**participant].is_removed()
+                    && !selections.is_empty()
+                    && selections.iter().all(|selection| selection.is_none())
+            })
+            .map(|(participant, _)| Reminder {
+                round: round,
+                hours_before_close: hours_before_close,
+                participant: *participant,
+            })
+            .collect()
+    }
+
+    //Opening a round finalizes every round before it. None closes selections without finalizing anything.
+    pub(crate) fn open_round(
+        &mut self,
+        round_index: Option<RoundIndex>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match round_index {
+            Some(round_index) => {
+                self.check_round_index(round_index)?;
+                if self.is_finalized(&round_index) {
+                    return Err(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Round {} is finalized and can't be reopened.", round_index),
+                    )));
+                }
+                if round_index > 0 {
+                    self.finalize_round(round_index - 1)?;
+                }
+            }
+            None => {}
+        };
+
+        self.current_open_round = round_index;
+        Ok(())
+    }
+
+    //Freezes this round and every round before it. Designations are settled first so the frozen results reflect the latest selections.
+    pub(crate) fn finalize_round(
+        &mut self,
+        round_index: RoundIndex,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.check_round_index(round_index)?;
+
+        self.determine_designations_from_current_selections();
+        for round in 0..round_index + 1 {
+            self.finalized_rounds.insert(round);
+        }
+
+        match self.current_open_round {
+            Some(current_open_round) => {
+                if self.is_finalized(&current_open_round) {
+                    self.current_open_round = None;
+                }
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    //Frees the participant's slot and ancillaries and keeps them out of the bucket for good.
+    //Open rounds are recomputed, so the next best pick takes the slot. Finalized rounds promote from the waitlist instead.
+    pub(crate) fn release_designation(
+        &mut self,
+        round: RoundIndex,
+        bucket_index: BucketIndex,
+        participant: ParticipantIndex,
+        release: Release,
+    ) -> Result<Vec<Promotion>, Box<dyn std::error::Error>> {
+        self.check_round_index(round)?;
+        let holds = self.bucket_states.get(bucket_index).map(|bucket_state| {
+            bucket_state
+                .get_state(&round)
+                .designations
+                .contains(&participant)
+        });
+        if holds != Some(true) {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Participant {} doesn't hold bucket {} in round {}.",
+                    participant, bucket_index, round
+                ),
+            )));
+        }
+
+        if self.is_finalized(&round) && !self.withdrawal_policy.allows(release.by) {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!(
+                    "Round {} is finalized and the withdrawal policy is {:?}.",
+                    round, self.withdrawal_policy
+                ),
+            )));
+        }
+
+        Ok(self.free_designation(round, bucket_index, participant, release))
+    }
+
+    //Frees a designation the participant holds, without asking the withdrawal policy. Returns whoever was promoted into it.
+    fn free_designation(
+        &mut self,
+        round: RoundIndex,
+        bucket_index: BucketIndex,
+        participant: ParticipantIndex,
+        release: Release,
+    ) -> Vec<Promotion> {
+        let before = self.all_designations();
+        let explanation = Explanation::Released(release.clone());
+        let round_state = self.bucket_states[bucket_index].get_state_mut(&round);
+        round_state.released.insert(participant, release);
+        round_state.overrides.remove(&participant);
+
+        if self.is_finalized(&round) {
+            self.bucket_states[bucket_index]
+                .get_state_mut(&round)
+                .release(&participant);
+            if let Some(selection) = self.find_granted_pick(round, participant, bucket_index) {
+                let granted = selection
+                    .granted_option
+                    .expect("Granted picks should have an option.");
+                selection.set_option_result(granted, SelectionResult::Released, explanation);
+            }
+            self.promote_from_waitlists(round, Vec::from([bucket_index]));
+        }
+        self.determine_designations_from_current_selections(); //Later rounds may change too
+
+        self.promotions_since(&before, |promoted| *promoted != participant)
+    }
+
+    //Overrides take their slot and ancillaries before any pick is considered, so recomputing never displaces them
+    pub(crate) fn assign_override(
+        &mut self,
+        round: RoundIndex,
+        bucket_index: BucketIndex,
+        participant: ParticipantIndex,
+        ancillaries: BTreeSet<AncillaryIndex>,
+        at: DateTime<Utc>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.check_round_index(round)?;
+        self.check_amendable_participant(&participant)?;
+        self.check_amendable_bucket(&bucket_index)?;
+        for ancillary in ancillaries.iter() {
+            if self
+                .basis
+                .resolve_ancillary(&bucket_index, ancillary)
+                .is_none()
+            {
+                return Err(invalid_amendment(&format!(
+                    "Invalid ancillary {} in bucket {}.",
+                    ancillary, bucket_index
+                )));
+            }
+        }
+
+        let finalized = self.is_finalized(&round);
+        let round_state = self.bucket_states[bucket_index].get_state_mut(&round);
+        round_state.released.remove(&participant);
+        round_state
+            .overrides
+            .insert(participant, ancillaries.clone());
+        if finalized {
+            round_state.release(&participant); //Drops ancillaries held before the override
+            round_state.apply_overrides();
+        }
+
+        self.override_log.push(OverrideRecord {
+            round: round,
+            bucket_index: bucket_index,
+            participant: participant,
+            ancillaries: ancillaries,
+            assigned: true,
+            at: at,
+        });
+        self.determine_designations_from_current_selections();
+        Ok(())
+    }
+
+    pub(crate) fn clear_override(
+        &mut self,
+        round: RoundIndex,
+        bucket_index: BucketIndex,
+        participant: ParticipantIndex,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<Promotion>, Box<dyn std::error::Error>> {
+        self.check_round_index(round)?;
+        let ancillaries = match self
+            .bucket_states
+            .get_mut(bucket_index)
+            .and_then(|bucket_state| {
+                bucket_state
+                    .get_state_mut(&round)
+                    .overrides
+                    .remove(&participant)
+            }) {
+            Some(ancillaries) => ancillaries,
+            None => {
+                return Err(invalid_amendment(&format!(
+                    "Participant {} has no override in bucket {} for round {}.",
+                    participant, bucket_index, round
+                )))
+            }
+        };
+
+        let before = self.all_designations();
+        if self.is_finalized(&round) {
+            self.bucket_states[bucket_index]
+                .get_state_mut(&round)
+                .release(&participant);
+            self.promote_from_waitlists(round, Vec::from([bucket_index]));
+        }
+
+        self.override_log.push(OverrideRecord {
+            round: round,
+            bucket_index: bucket_index,
+            participant: participant,
+            ancillaries: ancillaries,
+            assigned: false,
+            at: at,
+        });
+        self.determine_designations_from_current_selections();
+
+        Ok(self.promotions_since(&before, |promoted| *promoted != participant))
+    }
+
+    //Only additions draw ranks, and only for what was added. The lottery records everyone's weight, so later additions and verification use the same ones.
+    pub(crate) fn draw_amendment(
+        &self,
+        change: BasisAmendment,
+        at: DateTime<Utc>,
+        ledger: &Ledger,
+    ) -> Amendment {
+        let lottery = match &change {
+            BasisAmendment::AddParticipant(participant) => {
+                let mut amended = self.basis.clone();
+                amended
+                    .get_participant_definitions_mut()
+                    .push(participant.clone());
+                //Only the added participant's weight is new. Everyone else keeps the one they were drawn with.
+                let weights = amended.get_lottery_weights(ledger).map(|current| {
+                    let mut weights = self
+                        .drawn_weights()
+                        .unwrap_or_else(|| vec![1; current.len() - 1]);
+                    weights.push(
+                        *current
+                            .last()
+                            .expect("The added participant should have a weight."),
+                    );
+                    weights
+                });
+                Some(LotteryRecord::random().with_weights(weights))
+            }
+            BasisAmendment::AddBucket(_) => {
+                Some(LotteryRecord::random().with_weights(self.drawn_weights()))
+            }
+            _ => None,
+        };
+        Amendment {
+            change: change,
+            at: at,
+            lottery: lottery,
+        }
+    }
+
+    //Each participant's weight in the original draw, or in the draw that added them. None for a uniform lottery.
+    fn drawn_weights(&self) -> Option<Vec<u32>> {
+        let mut weights = self.lottery.as_ref()?.get_weights().clone()?;
+        for amendment in self.amendments.iter() {
+            if let BasisAmendment::AddParticipant(_) = amendment.change {
+                let weight = amendment
+                    .lottery
+                    .as_ref()
+                    .and_then(|lottery| lottery.get_weights().as_ref())
+                    .and_then(|added| added.last())
+                    .cloned()
+                    .unwrap_or(1);
+                weights.push(weight);
+            }
+        }
+        Some(weights)
+    }
+
+    //Every round that isn't finalized is recomputed
+    pub(crate) fn amend_basis(
+        &mut self,
+        amendment: Amendment,
+    ) -> Result<Vec<Promotion>, Box<dyn std::error::Error>> {
+        let mut amended = self.basis.clone();
+        let at = amendment.at;
+        match &amendment.change {
+            BasisAmendment::AddParticipant(participant) => {
+                amended
+                    .get_participant_definitions_mut()
+                    .push(participant.clone());
+            }
+            BasisAmendment::RemoveParticipant(participant) => {
+                self.check_amendable_participant(participant)?;
+                amended.get_participant_definitions_mut()[*participant].remove();
+            }
+            BasisAmendment::UpdateParticipant {
+                participant,
+                name,
+                email,
+            } => {
+                self.check_amendable_participant(participant)?;
+                amended.get_participant_definitions_mut()[*participant]
+                    .rename(name.clone(), email.clone());
+            }
+            BasisAmendment::SetPicks {
+                participant,
+                round,
+                picks,
+            } => {
+                self.check_amendable_participant(participant)?;
+                self.check_round_index(*round)?;
+                if self.is_finalized(round) {
+                    return Err(invalid_amendment(&format!("Round {} is finalized.", round)));
+                }
+                amended.get_participant_definitions_mut()[*participant].set_picks(*round, *picks);
+            }
+            BasisAmendment::AddBucket(bucket) => {
+                amended.get_bucket_definitions_mut().push(bucket.clone());
+            }
+            BasisAmendment::RemoveBucket(bucket_index) => {
+                self.check_amendable_bucket(bucket_index)?;
+                if self.finalized_holders(bucket_index) > 0 {
+                    return Err(invalid_amendment(&format!(
+                        "Bucket {} is held in a finalized round. Withdraw those designations first.",
+                        bucket_index
+                    )));
+                }
+                amended.get_bucket_definitions_mut()[*bucket_index].removed = true;
+            }
+            BasisAmendment::SetSlots {
+                bucket_index,
+                slots,
+            } => {
+                self.check_amendable_bucket(bucket_index)?;
+                if *slots < self.finalized_holders(bucket_index) {
+                    return Err(invalid_amendment(&format!(
+                        "Bucket {} has {} holders in finalized rounds.",
+                        bucket_index,
+                        self.finalized_holders(bucket_index)
+                    )));
+                }
+                amended.get_bucket_definitions_mut()[*bucket_index].available_slots = *slots;
+            }
+            BasisAmendment::SetSchedule { round, schedule } => {
+                self.check_round_index(*round)?;
+                if self.is_finalized(round) {
+                    return Err(invalid_amendment(&format!("Round {} is finalized.", round)));
+                }
+                amended.set_round_schedule(*round, schedule.clone());
+            }
+        }
+        amended.check()?;
+
+        let before = self.all_designations();
+        let backup = self.clone();
+        self.basis = amended;
+        match self.amend_state(&amendment, at) {
+            Ok(_) => {}
+            Err(e) => {
+                *self = backup;
+                return Err(e);
+            }
+        }
+        self.amendments.push(amendment);
+
+        Ok(self.promotions_since(&before, |promoted| {
+            !self.basis.get_participant_definitions()[*promoted].is_removed()
+        }))
+    }
+
+    //Brings ranks, selections and designations in line with the already amended basis
+    fn amend_state(
+        &mut self,
+        amendment: &Amendment,
+        at: DateTime<Utc>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let round_count = self.basis.get_selection_rounds().len();
+        match &amendment.change {
+            BasisAmendment::AddParticipant(participant) => {
+                let participant_index = self.basis.get_participant_definitions().len() - 1;
+                for round in 0..round_count {
+                    self.selections.set(
+                        round,
+                        participant_index,
+                        vec![None; participant.get_round_picks_allowed()[round]],
+                    );
+                }
+                self.extend_ranks(amendment)?;
+            }
+            BasisAmendment::AddBucket(_) => {
+                self.extend_ranks(amendment)?;
+            }
+            BasisAmendment::RemoveParticipant(participant) => {
+                for round in 0..round_count {
+                    if !self.is_finalized(&round) {
+                        if let Some(selections) = self
+                            .selections
+                            .get_mut(&round)
+                            .and_then(|round_selections| round_selections.get_mut(participant))
+                        {
+                            selections
+                                .iter_mut()
+                                .for_each(|selection| *selection = None);
+                        }
+                    }
+                }
+                for bucket_state in self.bucket_states.iter_mut() {
+                    for round_state in bucket_state.get_states_mut() {
+                        round_state
+                            .waitlist
+                            .retain(|entry| entry.participant != *participant);
+                    }
+                }
+                //A basis change rather than a withdrawal, so the withdrawal policy doesn't apply
+                for (round, bucket_index, holder) in self.all_designations() {
+                    if holder == *participant && self.is_finalized(&round) {
+                        self.free_designation(
+                            round,
+                            bucket_index,
+                            holder,
+                            Release::create(Releaser::Admin, at),
+                        );
+                    }
+                }
+            }
+            BasisAmendment::SetPicks {
+                participant,
+                round,
+                picks,
+            } => {
+                if let Some(selections) = self
+                    .selections
+                    .get_mut(round)
+                    .and_then(|round_selections| round_selections.get_mut(participant))
+                {
+                    selections.resize(*picks, None);
+                }
+            }
+            BasisAmendment::RemoveBucket(bucket_index) => {
+                for round_state in self.bucket_states[*bucket_index].get_states_mut() {
+                    round_state.waitlist.clear();
+                }
+            }
+            BasisAmendment::SetSlots { bucket_index, .. } => {
+                //New slots in a finalized round go to its waitlist, earliest round first
+                for round in self.finalized_rounds.clone() {
+                    while self.slots_available_this_round(bucket_index, &round)
+                        > self.bucket_states[*bucket_index]
+                            .get_state(&round)
+                            .designations
+                            .len()
+                    {
+                        let before = self.all_designations();
+                        self.promote_from_waitlists(round, Vec::from([*bucket_index]));
+                        if self.all_designations() == before {
+                            break;
+                        }
+                    }
+                }
+            }
+            BasisAmendment::SetSchedule { round, .. } => {
+                //A new time runs again, even if the old one already did
+                self.schedule_applied
+                    .retain(|change| change.round != *round);
+            }
+            BasisAmendment::UpdateParticipant { .. } => {}
+        }
+
+        self.determine_designations_from_current_selections();
+        Ok(())
+    }
+
+    //Draws ranks for an added participant or bucket. Everyone else keeps their order.
+    fn extend_ranks(&mut self, amendment: &Amendment) -> Result<(), Box<dyn std::error::Error>> {
+        let mut ranks: Vec<Vec<Ranks>> = self
+            .bucket_states
+            .iter()
+            .map(|bucket_state| {
+                bucket_state
+                    .get_states()
+                    .iter()
+                    .map(|round_state| round_state.ranks.clone().expect("Ranks should exist."))
+                    .collect()
+            })
+            .collect();
+        amendment.extend_ranks(
+            &mut ranks,
+            self.basis.get_allocation(),
+            self.basis.get_participant_definitions().len(),
+            self.basis.get_selection_rounds().len(),
+        )?;
+
+        while self.bucket_states.len() < ranks.len() {
+            self.bucket_states.push(RoundStates::new(&self.basis));
+        }
+        for (bucket, bucket_ranks) in self.bucket_states.iter_mut().zip(ranks) {
+            for (round, round_ranks) in bucket_ranks.into_iter().enumerate() {
+                bucket.get_state_mut(&round).ranks = Some(round_ranks);
+            }
+        }
+        Ok(())
+    }
+
+    //Designations of the bucket across every finalized round, which an amendment can't take away
+    fn finalized_holders(&self, bucket_index: &BucketIndex) -> usize {
+        self.finalized_rounds
+            .iter()
+            .map(|round| {
+                self.bucket_states[*bucket_index]
+                    .get_state(round)
+                    .designations
+                    .len()
+            })
+            .sum()
+    }
+
+    fn check_amendable_participant(
+        &self,
+        participant: &ParticipantIndex,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self.basis.get_participant_definitions().get(*participant) {
+            Some(participant_def) if participant_def.is_removed() => Err(invalid_amendment(
+                &format!("Participant {} has been removed.", participant),
+            )),
+            Some(_) => Ok(()),
+            None => Err(invalid_amendment(&format!(
+                "No participant {}.",
+                participant
+            ))),
+        }
+    }
+
+    fn check_amendable_bucket(
+        &self,
+        bucket_index: &BucketIndex,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self.basis.get_bucket_definitions().get(*bucket_index) {
+            Some(bucket) if bucket.removed => Err(invalid_amendment(&format!(
+                "Bucket {} has been removed.",
+                bucket_index
+            ))),
+            Some(_) => Ok(()),
+            None => Err(invalid_amendment(&format!("No bucket {}.", bucket_index))),
+        }
+    }
+
+    pub(crate) fn add_swap_proposal(
+        &mut self,
+        proposer: ParticipantIndex,
+        proposer_holding: Holding,
+        recipient: ParticipantIndex,
+        recipient_holding: Holding,
+        at: DateTime<Utc>,
+    ) -> Result<SwapIndex, Box<dyn std::error::Error>> {
+        if proposer == recipient {
+            return Err(invalid_swap("A participant can't swap with themselves."));
+        }
+        self.check_holding(&proposer, &proposer_holding)?;
+        self.check_holding(&recipient, &recipient_holding)?;
+
+        self.swaps.push(SwapProposal {
+            proposer: proposer,
+            proposer_holding: proposer_holding,
+            recipient: recipient,
+            recipient_holding: recipient_holding,
+            status: SwapStatus::Proposed,
+            proposed_at: at,
+            resolved_at: None,
+        });
+        Ok(self.swaps.len() - 1)
+    }
+
+    pub(crate) fn resolve_swap(
+        &mut self,
+        participant: ParticipantIndex,
+        swap_index: SwapIndex,
+        accept: bool,
+        at: DateTime<Utc>,
+    ) -> Result<SwapStatus, Box<dyn std::error::Error>> {
+        let proposal = match self.swaps.get(swap_index) {
+            Some(proposal) if proposal.status == SwapStatus::Proposed => proposal.clone(),
+            Some(proposal) => {
+                return Err(invalid_swap(&format!(
+                    "Swap {} is already {:?}.",
+                    swap_index, proposal.status
+                )))
+            }
+            None => return Err(invalid_swap(&format!("No swap {}.", swap_index))),
+        };
+
+        let status = if participant == proposal.recipient && accept {
+            self.apply_swap(swap_index, &proposal)?;
+            SwapStatus::Accepted
+        } else if participant == proposal.recipient {
+            SwapStatus::Declined
+        } else if participant == proposal.proposer && !accept {
+            SwapStatus::Cancelled
+        } else {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("Only the recipient of swap {} can accept it.", swap_index),
+            )));
+        };
+
+        let resolved = &mut self.swaps[swap_index];
+        resolved.status = status;
+        resolved.resolved_at = Some(at);
+        Ok(status)
+    }
+
+    //Only finalized rounds, since recomputing an open round would undo the swap
+    fn check_holding(
+        &self,
+        participant: &ParticipantIndex,
+        holding: &Holding,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.check_round_index(holding.round)?;
+        if !self.is_finalized(&holding.round) {
+            return Err(invalid_swap(&format!(
+                "Round {} isn't finalized, so it can't be swapped.",
+                holding.round
+            )));
+        }
+        let holds = self
+            .bucket_states
+            .get(holding.bucket_index)
+            .map(|bucket_state| {
+                bucket_state
+                    .get_state(&holding.round)
+                    .designations
+                    .contains(participant)
+            });
+        match holds {
+            Some(true)
+                if self.bucket_states[holding.bucket_index]
+                    .get_state(&holding.round)
+                    .overrides
+                    .contains_key(participant) =>
+            {
+                Err(invalid_swap(&format!(
+                    "Participant {} was placed in bucket {} by an admin, so it can't be swapped.",
+                    participant, holding.bucket_index
+                )))
+            }
+            Some(true) => Ok(()),
+            _ => Err(invalid_swap(&format!(
+                "Participant {} doesn't hold bucket {} in round {}.",
+                participant, holding.bucket_index, holding.round
+            ))),
+        }
+    }
+
+    //All or nothing. Each participant takes over the other's slot and ancillaries, so capacities are unchanged, but eligibility, overlaps and quotas are checked again.
+    //Rounds that aren't finalized are recomputed, since they depend on what's held in earlier rounds.
+    fn apply_swap(
+        &mut self,
+        swap_index: SwapIndex,
+        proposal: &SwapProposal,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let bucket_states_backup = self.bucket_states.clone();
+        let selections_backup = self.selections.clone();
+        let result = self.exchange_holdings(swap_index, proposal);
+        match result {
+            Ok(()) => self.determine_designations_from_current_selections(),
+            Err(_) => {
+                self.bucket_states = bucket_states_backup;
+                self.selections = selections_backup;
+            }
+        }
+        result
+    }
+
+    fn exchange_holdings(
+        &mut self,
+        swap_index: SwapIndex,
+        proposal: &SwapProposal,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.check_holding(&proposal.proposer, &proposal.proposer_holding)?;
+        self.check_holding(&proposal.recipient, &proposal.recipient_holding)?;
+
+        let proposer_ancillaries =
+            self.take_holding(&proposal.proposer, &proposal.proposer_holding);
+        let recipient_ancillaries =
+            self.take_holding(&proposal.recipient, &proposal.recipient_holding);
+        self.give_holding(
+            &proposal.recipient,
+            &proposal.proposer_holding,
+            proposer_ancillaries,
+        )?;
+        self.give_holding(
+            &proposal.proposer,
+            &proposal.recipient_holding,
+            recipient_ancillaries,
+        )?;
+
+        self.record_swap(swap_index, &proposal.proposer, &proposal.proposer_holding);
+        self.record_swap(swap_index, &proposal.recipient, &proposal.recipient_holding);
+        Ok(())
+    }
+
+    //The pick that held the given bucket ends as swapped away. The received bucket is only a designation, with the accepted swap as its record, so the round keeps the picks it allows.
+    fn record_swap(
+        &mut self,
+        swap_index: SwapIndex,
+        participant: &ParticipantIndex,
+        given: &Holding,
+    ) {
+        if let Some(selection) =
+            self.find_granted_pick(given.round, *participant, given.bucket_index)
+        {
+            let granted = selection
+                .granted_option
+                .expect("Granted picks should have an option.");
+            selection.set_option_result(
+                granted,
+                SelectionResult::SwappedAway,
+                Explanation::SwappedAway {
+                    swap_index: swap_index,
+                },
+            );
+        }
+    }
+
+    //Releases the holding and returns the ancillaries that went with it
+    fn take_holding(
+        &mut self,
+        participant: &ParticipantIndex,
+        holding: &Holding,
+    ) -> BTreeSet<AncillaryIndex> {
+        let round_state = self.bucket_states[holding.bucket_index].get_state_mut(&holding.round);
+        let ancillaries = round_state
+            .ancillary_designations
+            .iter()
+            .filter(|(_, holders)| holders.contains(participant))
+            .map(|(ancillary, _)| *ancillary)
+            .collect();
+        round_state.release(participant);
+        ancillaries
+    }
+
+    fn give_holding(
+        &mut self,
+        participant: &ParticipantIndex,
+        holding: &Holding,
+        ancillaries: BTreeSet<AncillaryIndex>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let bucket_definitions = self.basis.get_bucket_definitions();
+        let bucket = &bucket_definitions[holding.bucket_index];
+        for held_index in self.get_holdings(participant).keys() {
+            if *held_index == holding.bucket_index {
+                return Err(invalid_swap(&format!(
+                    "Participant {} already holds {}.",
+                    participant, bucket.name
+                )));
+            }
+            if bucket_definitions[*held_index].overlaps(bucket) {
+                return Err(invalid_swap(&format!(
+                    "{} overlaps {}, which participant {} already holds.",
+                    bucket.name, bucket_definitions[*held_index].name, participant
+                )));
+            }
+        }
+        for ancillary in ancillaries.iter() {
+            match self
+                .basis
+                .resolve_ancillary(&holding.bucket_index, ancillary)
+            {
+                Some((definition, _)) if definition.is_eligible(participant) => {}
+                _ => {
+                    return Err(invalid_swap(&format!(
+                        "Participant {} isn't eligible for ancillary {} of {}.",
+                        participant, ancillary, bucket.name
+                    )))
+                }
+            }
+        }
+
+        let quota_weight = bucket.quota_weight;
+        let max_buckets = self.basis.get_participant_definitions()[*participant].get_max_buckets();
+        if let Some(max_buckets) = max_buckets {
+            if self.get_quota_used(participant) + quota_weight > max_buckets {
+                return Err(invalid_swap(&format!(
+                    "Participant {} would be over their maximum of {} buckets.",
+                    participant, max_buckets
+                )));
+            }
+        }
+
+        let round_state = self.bucket_states[holding.bucket_index].get_state_mut(&holding.round);
+        round_state.designations.insert(*participant);
+        for ancillary in ancillaries {
+            round_state
+                .ancillary_designations
+                .entry(ancillary)
+                .or_default()
+                .insert(*participant);
+        }
+        Ok(())
+    }
+
+    //Designations that didn't exist before, for the participants the filter keeps
+    fn promotions_since<F>(
+        &self,
+        before: &BTreeSet<(RoundIndex, BucketIndex, ParticipantIndex)>,
+        include: F,
+    ) -> Vec<Promotion>
+    where
+        F: Fn(&ParticipantIndex) -> bool,
+    {
+        self.all_designations()
+            .difference(before)
+            .filter(|(_, _, promoted)| include(promoted))
+            .map(|(round, bucket_index, promoted)| Promotion {
+                round: *round,
+                bucket_index: *bucket_index,
+                participant: *promoted,
+                notified: false,
+            })
+            .collect()
+    }
+
+    fn all_designations(&self) -> BTreeSet<(RoundIndex, BucketIndex, ParticipantIndex)> {
+        let mut retval = BTreeSet::new();
+        for (bucket_index, bucket_state) in self.bucket_states.iter().enumerate() {
+            for (round, round_state) in bucket_state.get_states().iter().enumerate() {
+                for participant in round_state.designations.iter() {
+                    retval.insert((round, bucket_index, *participant));
+                }
+            }
+        }
+        retval
+    }
+
+    //The participant's pick that was granted this bucket
+    fn find_granted_pick(
+        &mut self,
+        round: RoundIndex,
+        participant: ParticipantIndex,
+        bucket_index: BucketIndex,
+    ) -> Option<&mut Selection> {
+        self.selections
+            .get_mut(&round)?
+            .get_mut(&participant)?
+            .iter_mut()
+            .flatten()
+            .find(|selection| match selection.granted_option {
+                Some(granted) => selection.get_option(granted).0 == bucket_index,
+                None => false,
+            })
+    }
+
+    //Fills each freed bucket with the best waitlisted pick that still fits. A promoted pick gives up the later option it held, which frees that bucket in turn.
+    fn promote_from_waitlists(&mut self, round: RoundIndex, mut freed: Vec<BucketIndex>) {
+        while let Some(bucket_index) = freed.pop() {
+            let waitlist = self.bucket_states[bucket_index]
+                .get_state(&round)
+                .waitlist
+                .clone();
+            for entry in waitlist {
+                if let Some(given_up) = self.try_promote(round, bucket_index, &entry) {
+                    self.bucket_states[bucket_index]
+                        .get_state_mut(&round)
+                        .waitlist
+                        .retain(|listed| *listed != entry);
+                    freed.extend(given_up);
+                    break;
+                }
+            }
+        }
+    }
+
+    //None if the waitlisted pick can't have the bucket, leaving the state as it was. Otherwise the bucket the pick gave up, if any.
+    fn try_promote(
+        &mut self,
+        round: RoundIndex,
+        bucket_index: BucketIndex,
+        entry: &WaitlistEntry,
+    ) -> Option<Option<BucketIndex>> {
+        let round_state = self.bucket_states[bucket_index].get_state(&round);
+        if round_state.designations.contains(&entry.participant)
+            || round_state.released.contains_key(&entry.participant)
+        {
+            return None;
+        }
+
+        let selection = self
+            .selections
+            .get(&round)?
+            .get(&entry.participant)?
+            .get(entry.pick_index)?
+            .clone()?;
+        if entry.option_index >= selection.option_count()
+            || selection.get_option(entry.option_index).0 != bucket_index
+        {
+            return None;
+        }
+        let given_up = match selection.granted_option {
+            Some(granted) if granted <= entry.option_index => return None, //Already has something they wanted more
+            Some(granted) => Some(selection.get_option(granted).0),
+            None => None,
+        };
+
+        let backup = self.bucket_states.clone();
+        if let Some(given_up) = given_up {
+            self.bucket_states[given_up]
+                .get_state_mut(&round)
+                .release(&entry.participant);
+        }
+
+        //Everyone left keeps their place, so the promoted pick only takes what's free
+        let locked: Vec<Designations> = self
+            .bucket_states
+            .iter()
+            .map(|bucket_state| bucket_state.get_state(&round).designations.clone())
+            .collect();
+        let (_, ancillaries) = selection.get_option(entry.option_index);
+        let conflicts = self.calendar_conflicts(&round, &entry.participant, bucket_index, &locked);
+        let result = match conflicts.is_empty() {
+            true => {
+                self.attempt_selection(
+                    &round,
+                    &entry.participant,
+                    bucket_index,
+                    ancillaries,
+                    &locked,
+                )
+                .0
+            }
+            false => SelectionResult::RejectedCalendarConflict(conflicts),
+        };
+        if result != SelectionResult::Confirmed {
+            self.bucket_states = backup;
+            return None;
+        }
+
+        let granted = self.explain(
+            &round,
+            &entry.participant,
+            bucket_index,
+            &SelectionResult::Confirmed,
+        );
+        let selection = self
+            .selections
+            .get_mut(&round)?
+            .get_mut(&entry.participant)?[entry.pick_index]
+            .as_mut()?;
+        if let Some(given_up) = selection.granted_option {
+            selection.set_option_result(
+                given_up,
+                SelectionResult::Released,
+                Explanation::GivenUp {
+                    option_index: entry.option_index,
+                },
+            );
+        }
+        selection.set_option_result(entry.option_index, SelectionResult::Confirmed, granted);
+        Some(given_up)
+    }
+
+    pub fn is_finalized(&self, round_index: &RoundIndex) -> bool {
+        self.finalized_rounds.contains(round_index)
+    }
+
+    //Every round is finalized
+    pub fn is_complete(&self) -> bool {
+        (0..self.basis.get_selection_rounds().len()).all(|round| self.is_finalized(&round))
+    }
+
+    fn check_round_index(&self, round_index: RoundIndex) -> Result<(), Box<dyn std::error::Error>> {
+        if round_index < self.basis.get_selection_rounds().len() {
+            Ok(())
+        } else {
+            Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid round index {}.", round_index),
+            )))
+        }
+    }
+}
+
+fn invalid_amendment(message: &str) -> Box<dyn std::error::Error> {
+    Box::new(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        message.to_string(),
+    ))
+}
+
+fn invalid_swap(message: &str) -> Box<dyn std::error::Error> {
+    Box::new(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        message.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, TimeZone};
+
+    use crate::{
+        db::memory::InMemoryDivisionStore,
+        division::{
+            ancillary::{AncillaryDef, BucketAncillary},
+            round::RoundSchedule,
+            strategy::Allocation,
+        },
+    };
+
+    use super::*;
+
+    const PARTICIPANT_A: (usize, &str, &str) = (0, "Participant A", "testing_a@autoscheda.com");
+    const PARTICIPANT_B: (usize, &str, &str) = (1, "Participant B", "testing_b@autoscheda.com");
+    const PARTICIPANT_C: (usize, &str, &str) = (2, "Participant C", "testing_c@autoscheda.com");
+    const BLACK_BUTTE: (usize, &str) = (0, "Black Butte");
+    const BUCKET_INDICES: [usize; 6] = [0, 1, 2, 3, 4, 5];
+
+    const ROUND_1: (usize, &str) = (0, "Predesignation");
+    const ROUND_2: (usize, &str) = (1, "Round 1");
+    const ROUND_3: (usize, &str) = (2, "Round 2");
+    const ROUND_4: (usize, &str) = (3, "Round 3");
+
+    const PICKS_PER_ROUND: usize = 1;
+    const OVERLAPPING_BUCKET: usize = 5;
+
+    fn first_day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, 6).expect("Should be a date.")
+    }
+
+    fn bucketname(i: usize) -> String {
+        "Bucket ".to_string() + &i.to_string()
+    }
+
+    fn create_basis() -> BlockDivisionBasis {
+        create_basis_with_slots(5)
+    }
+
+    fn create_basis_with_slots(slots: usize) -> BlockDivisionBasis {
+        create_basis_with_picks(slots, PICKS_PER_ROUND)
+    }
+
+    fn create_basis_with_picks(slots: usize, picks_per_round: usize) -> BlockDivisionBasis {
+        create_basis_with_ancillaries(
+            slots,
+            picks_per_round,
+            BucketAncillary::Named(BLACK_BUTTE.1.to_string()),
+        )
+    }
+
+    //Every bucket offers the same single ancillary
+    fn create_basis_with_ancillaries(
+        slots: usize,
+        picks_per_round: usize,
+        ancillary: BucketAncillary,
+    ) -> BlockDivisionBasis {
+        let mut buckets: Vec<BucketDef> = Vec::new();
+
+        for n in BUCKET_INDICES {
+            //Consecutive weeks, except the last bucket which straddles the first two
+            let start = match n == OVERLAPPING_BUCKET {
+                true => first_day() + Duration::days(3),
+                false => first_day() + Duration::weeks(n as i64),
+            };
+            buckets.insert(
+                n,
+                BucketDef {
+                    name: bucketname(n),
+                    available_slots: slots,
+                    available_ancillaries: Vec::from([ancillary.clone()]),
+                    start: Some(start),
+                    end: Some(start + Duration::days(6)),
+                    holiday: n == 0,
+                    quota_weight: 1,
+                    removed: false,
+                },
+            );
+        }
+
+        let rounds: Vec<RoundName> = Vec::from([
+            ROUND_1.1.to_string(),
+            ROUND_2.1.to_string(),
+            ROUND_3.1.to_string(),
+            ROUND_4.1.to_string(),
+        ]);
+
+        let mut round_picks: Vec<usize> = Vec::new();
+        for n in 0..rounds.len() {
+            round_picks.insert(n, picks_per_round);
+        }
+
+        let mut participants: Vec<ParticipantDef> = Vec::new();
+
+        for participant in [PARTICIPANT_A, PARTICIPANT_B, PARTICIPANT_C] {
+            participants.insert(
+                participant.0,
+                ParticipantDef::create(
+                    participant.1.to_string(),
+                    participant.2.to_string(),
+                    round_picks.clone(),
+                ),
+            );
+        }
+
+        BlockDivisionBasis::create(buckets, participants, rounds)
+    }
+
+    //Overwrites the drawn ranks for participants A, B and C so outcomes are predictable
+    //Amends the way the store does, drawing any lottery with an empty ledger
+    fn amend(
+        bds: &mut BlockDivisionState,
+        change: BasisAmendment,
+    ) -> Result<Vec<Promotion>, Box<dyn std::error::Error>> {
+        let amendment = bds.draw_amendment(change, Utc::now(), &Ledger::new());
+        bds.amend_basis(amendment)
+    }
+
+    fn set_ranks(bds: &mut BlockDivisionState, bucket: usize, round: usize, ranks: [usize; 3]) {
+        bds.bucket_states
+            .get_mut(bucket)
+            .expect("Should exist.")
+            .get_state_mut(&round)
+            .ranks = Some(Ranks::from([
+            (PARTICIPANT_A.0, ranks[0]),
+            (PARTICIPANT_B.0, ranks[1]),
+            (PARTICIPANT_C.0, ranks[2]),
+        ]));
+    }
+
+    fn pick(bucket_index: usize, fallbacks: &[usize]) -> Option<Selection> {
+        Some(Selection {
+            bucket_index: bucket_index,
+            ancillaries: BTreeSet::new(),
+            fallbacks: fallbacks
+                .iter()
+                .map(|fallback| SelectionOption {
+                    bucket_index: *fallback,
+                    ancillaries: BTreeSet::new(),
+                    state: None,
+                })
+                .collect(),
+            state: None,
+            granted_option: None,
+            explanations: BTreeMap::new(),
+        })
+    }
+
+    fn get_pick(bds: &BlockDivisionState, round: usize, participant: usize) -> &Selection {
+        get_nth_pick(bds, round, participant, 0)
+    }
+
+    fn get_nth_pick(
+        bds: &BlockDivisionState,
+        round: usize,
+        participant: usize,
+        pick_index: usize,
+    ) -> &Selection {
+        bds.selections
+            .get(&round)
+            .expect("Should exist.")
+            .get(&participant)
+            .expect("Should exist.")
+            .get(pick_index)
+            .expect("Should exist.")
+            .as_ref()
+            .expect("Should be some.")
+    }
+
+    fn get_designations(bds: &BlockDivisionState, bucket: usize, round: usize) -> &Designations {
+        &bds.bucket_states
+            .get(bucket)
+            .expect("Should exist.")
+            .get_state(&round)
+            .designations
+    }
+
+    #[test]
+    fn fallback_after_outranked() {
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        let round = ROUND_1.0;
+        set_ranks(&mut bds, 0, round, [1, 2, 3]);
+
+        bds.selections
+            .set(round, PARTICIPANT_A.0, Vec::from([pick(0, &[1])]));
+        bds.selections
+            .set(round, PARTICIPANT_B.0, Vec::from([pick(0, &[1, 2])]));
+        bds.determine_designations_from_current_selections();
+
+        let pick_a = get_pick(&bds, round, PARTICIPANT_A.0);
+        assert!(pick_a.state == Some(SelectionResult::Confirmed));
+        assert!(pick_a.granted_option == Some(0));
+
+        let pick_b = get_pick(&bds, round, PARTICIPANT_B.0);
+        assert!(pick_b.state == Some(SelectionResult::Confirmed));
+        assert!(pick_b.granted_option == Some(1));
+        assert!(pick_b.fallbacks[0].state == Some(SelectionResult::Confirmed));
+        assert!(pick_b.fallbacks[1].state.is_none()); //Never needed
+
+        assert!(*get_designations(&bds, 0, round) == Designations::from([PARTICIPANT_A.0]));
+        assert!(*get_designations(&bds, 1, round) == Designations::from([PARTICIPANT_B.0]));
+    }
+
+    #[test]
+    fn fallback_displaces_worse_rank() {
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        let round = ROUND_1.0;
+        set_ranks(&mut bds, 0, round, [2, 1, 3]);
+        set_ranks(&mut bds, 1, round, [3, 2, 1]);
+
+        //C takes bucket 1 from B, so B falls back to bucket 0 where B outranks A, who has no fallback
+        bds.selections
+            .set(round, PARTICIPANT_A.0, Vec::from([pick(0, &[])]));
+        bds.selections
+            .set(round, PARTICIPANT_B.0, Vec::from([pick(1, &[0])]));
+        bds.selections
+            .set(round, PARTICIPANT_C.0, Vec::from([pick(1, &[])]));
+        bds.determine_designations_from_current_selections();
+
+        let pick_a = get_pick(&bds, round, PARTICIPANT_A.0);
+        assert!(pick_a.state == Some(SelectionResult::RejectedOutranked));
+        assert!(pick_a.granted_option.is_none());
+
+        let pick_b = get_pick(&bds, round, PARTICIPANT_B.0);
+        assert!(pick_b.state == Some(SelectionResult::Confirmed));
+        assert!(pick_b.granted_option == Some(1));
+
+        let pick_c = get_pick(&bds, round, PARTICIPANT_C.0);
+        assert!(pick_c.state == Some(SelectionResult::Confirmed));
+
+        assert!(*get_designations(&bds, 0, round) == Designations::from([PARTICIPANT_B.0]));
+        assert!(*get_designations(&bds, 1, round) == Designations::from([PARTICIPANT_C.0]));
+    }
+
+    #[test]
+    fn multi_pick_results_land_on_their_pick() {
+        let mut bds =
+            BlockDivisionState::new(&create_basis_with_picks(1, 2)).expect("Should create.");
+        let round = ROUND_1.0;
+        set_ranks(&mut bds, 0, round, [2, 1, 3]);
+        set_ranks(&mut bds, 1, round, [1, 2, 3]);
+
+        //B outranks A for bucket 0, but A's first pick is settled before B's second pick can contest it
+        bds.selections.set(
+            round,
+            PARTICIPANT_A.0,
+            Vec::from([pick(0, &[]), pick(1, &[])]),
+        );
+        bds.selections.set(
+            round,
+            PARTICIPANT_B.0,
+            Vec::from([pick(1, &[]), pick(0, &[])]),
+        );
+        bds.determine_designations_from_current_selections();
+
+        assert!(
+            get_nth_pick(&bds, round, PARTICIPANT_A.0, 0).state == Some(SelectionResult::Confirmed)
+        );
+        assert!(
+            get_nth_pick(&bds, round, PARTICIPANT_A.0, 1).state
+                == Some(SelectionResult::RejectedNoSelectionsThisRound)
+        );
+        assert!(
+            get_nth_pick(&bds, round, PARTICIPANT_B.0, 0).state == Some(SelectionResult::Confirmed)
+        );
+        assert!(
+            get_nth_pick(&bds, round, PARTICIPANT_B.0, 1).state
+                == Some(SelectionResult::RejectedNoSelectionsThisRound)
+        );
+
+        assert!(*get_designations(&bds, 0, round) == Designations::from([PARTICIPANT_A.0]));
+        assert!(*get_designations(&bds, 1, round) == Designations::from([PARTICIPANT_B.0]));
+    }
+
+    #[test]
+    fn later_picks_skip_held_buckets() {
+        let mut bds =
+            BlockDivisionState::new(&create_basis_with_picks(2, 3)).expect("Should create.");
+        let round = ROUND_1.0;
+        set_ranks(&mut bds, 0, round, [1, 2, 3]);
+        set_ranks(&mut bds, 1, round, [3, 2, 1]);
+
+        //A asks for bucket 0 twice, but falls back to bucket 1 the second time. C has no fallback.
+        bds.selections.set(
+            round,
+            PARTICIPANT_A.0,
+            Vec::from([pick(0, &[]), pick(0, &[1]), None]),
+        );
+        bds.selections.set(
+            round,
+            PARTICIPANT_C.0,
+            Vec::from([pick(1, &[]), pick(1, &[]), None]),
+        );
+        bds.determine_designations_from_current_selections();
+
+        assert!(get_nth_pick(&bds, round, PARTICIPANT_A.0, 0).granted_option == Some(0));
+        let second_a = get_nth_pick(&bds, round, PARTICIPANT_A.0, 1);
+        assert!(second_a.state == Some(SelectionResult::Confirmed));
+        assert!(second_a.granted_option == Some(1));
+
+        assert!(
+            get_nth_pick(&bds, round, PARTICIPANT_C.0, 0).state == Some(SelectionResult::Confirmed)
+        );
+        assert!(
+            get_nth_pick(&bds, round, PARTICIPANT_C.0, 1).state
+                == Some(SelectionResult::RejectedAlreadyDesignated)
+        );
+
+        assert!(*get_designations(&bds, 0, round) == Designations::from([PARTICIPANT_A.0]));
+        assert!(
+            *get_designations(&bds, 1, round)
+                == Designations::from([PARTICIPANT_A.0, PARTICIPANT_C.0])
+        );
+    }
+
+    #[test]
+    fn overlapping_buckets_conflict() {
+        let mut bds =
+            BlockDivisionState::new(&create_basis_with_picks(1, 2)).expect("Should create.");
+        set_ranks(&mut bds, 0, ROUND_1.0, [1, 2, 3]);
+
+        //A holds the first week from round 1, so can't take the straddling bucket in round 2 and falls back
+        bds.selections
+            .set(ROUND_1.0, PARTICIPANT_A.0, Vec::from([pick(0, &[]), None]));
+        bds.selections.set(
+            ROUND_2.0,
+            PARTICIPANT_A.0,
+            Vec::from([pick(OVERLAPPING_BUCKET, &[2]), None]),
+        );
+        //B's second pick overlaps B's first pick in the same round
+        bds.selections.set(
+            ROUND_2.0,
+            PARTICIPANT_B.0,
+            Vec::from([pick(1, &[]), pick(OVERLAPPING_BUCKET, &[])]),
+        );
+        bds.determine_designations_from_current_selections();
+
+        let pick_a = get_pick(&bds, ROUND_2.0, PARTICIPANT_A.0);
+        assert!(pick_a.granted_option == Some(1));
+        assert!(*get_designations(&bds, 2, ROUND_2.0) == Designations::from([PARTICIPANT_A.0]));
+
+        assert!(
+            get_pick(&bds, ROUND_2.0, PARTICIPANT_B.0).state == Some(SelectionResult::Confirmed)
+        );
+        assert!(
+            get_nth_pick(&bds, ROUND_2.0, PARTICIPANT_B.0, 1).state
+                == Some(SelectionResult::RejectedCalendarConflict(Vec::from([1])))
+        );
+        assert!(get_designations(&bds, OVERLAPPING_BUCKET, ROUND_2.0).is_empty());
+    }
+
+    #[test]
+    fn quota_counts_bucket_weight_across_rounds() {
+        let basis = create_basis_with_picks(5, 2);
+        let mut buckets = basis.get_bucket_definitions().clone();
+        buckets[2].quota_weight = 2;
+        let mut participants = basis.get_participant_definitions().clone();
+        participants[PARTICIPANT_A.0] = participants[PARTICIPANT_A.0]
+            .clone()
+            .with_max_buckets(Some(2));
+        let mut bds = BlockDivisionState::new(&BlockDivisionBasis::create(
+            buckets,
+            participants,
+            basis.get_selection_rounds().clone(),
+        ))
+        .expect("Should create.");
+
+        //Bucket 2 would put A over, so A falls back to bucket 3. That fills A's quota for later rounds.
+        bds.selections.set(
+            ROUND_1.0,
+            PARTICIPANT_A.0,
+            Vec::from([pick(0, &[]), pick(2, &[3])]),
+        );
+        bds.selections
+            .set(ROUND_2.0, PARTICIPANT_A.0, Vec::from([pick(1, &[]), None]));
+        bds.selections
+            .set(ROUND_2.0, PARTICIPANT_B.0, Vec::from([pick(1, &[]), None]));
+        bds.determine_designations_from_current_selections();
+
+        let second = get_nth_pick(&bds, ROUND_1.0, PARTICIPANT_A.0, 1);
+        assert!(second.state == Some(SelectionResult::Confirmed));
+        assert!(second.granted_option == Some(1));
+        assert!(
+            get_pick(&bds, ROUND_2.0, PARTICIPANT_A.0).state
+                == Some(SelectionResult::RejectedQuotaReached)
+        );
+        assert!(
+            get_pick(&bds, ROUND_2.0, PARTICIPANT_B.0).state == Some(SelectionResult::Confirmed)
+        );
+        assert!(bds.get_quota_used(&PARTICIPANT_A.0) == 2);
+    }
+
+    #[test]
+    fn shared_ancillary_capacity() {
+        let boat = AncillaryDef {
+            name: "Boat".to_string(),
+            capacity: 2,
+            eligible_participants: None,
+        };
+        let basis = create_basis_with_ancillaries(3, 1, BucketAncillary::Shared { shared: 0 })
+            .with_ancillary_definitions(Vec::from([boat]));
+        let mut bds = BlockDivisionState::new(&basis).expect("Should create.");
+        let round = ROUND_1.0;
+        set_ranks(&mut bds, 0, round, [3, 2, 1]);
+        set_ranks(&mut bds, 1, round, [3, 2, 1]);
+        set_ranks(&mut bds, 2, round, [3, 2, 1]);
+
+        let locked: Vec<Designations> = Vec::from([(); 6].map(|_| Designations::new()));
+        let boat = BTreeSet::from([0]);
+        let (result, displaced) =
+            bds.attempt_selection(&round, &PARTICIPANT_A.0, 0, &boat, &locked);
+        assert!(result == SelectionResult::Confirmed && displaced.is_empty());
+        let (result, displaced) =
+            bds.attempt_selection(&round, &PARTICIPANT_B.0, 1, &boat, &locked);
+        assert!(result == SelectionResult::Confirmed && displaced.is_empty());
+
+        //The boat is full, so C bumps the worst ranked holder out of a different bucket
+        let (result, displaced) =
+            bds.attempt_selection(&round, &PARTICIPANT_C.0, 2, &boat, &locked);
+        assert!(result == SelectionResult::Confirmed);
+        assert!(
+            displaced
+                == Vec::from([(
+                    0,
+                    PARTICIPANT_A.0,
+                    SelectionResult::RejectedAncillaryUnavailable(Vec::from([0]))
+                )])
+        );
+        assert!(get_designations(&bds, 0, round).is_empty());
+        assert!(bds.bucket_states[0]
+            .get_state(&round)
+            .ancillary_designations
+            .is_empty());
+
+        //And A can't take it back
+        let (result, _) = bds.attempt_selection(&round, &PARTICIPANT_A.0, 0, &boat, &locked);
+        assert!(result == SelectionResult::RejectedAncillaryUnavailable(Vec::from([0])));
+        assert!(bds.get_holdings(&PARTICIPANT_B.0) == BTreeMap::from([(1, boat.clone())]));
+        assert!(bds.get_holdings(&PARTICIPANT_C.0) == BTreeMap::from([(2, boat.clone())]));
+    }
+
+    #[test]
+    fn ancillary_eligibility() {
+        let cabin = AncillaryDef {
+            name: "Cabin".to_string(),
+            capacity: 2,
+            eligible_participants: Some(BTreeSet::from([PARTICIPANT_A.0])),
+        };
+        let mut store = InMemoryDivisionStore::new();
+        let id = "Test Block Division Eligibility";
+        store
+            .new_division(
+                id,
+                &create_basis_with_ancillaries(5, 1, BucketAncillary::Defined(cabin)),
+            )
+            .expect("Should work.");
+        BlockDivisionState::set_open_round(&mut store, id.to_string(), Some(ROUND_1.0))
+            .expect("Couldn't set open round.");
+
+        let mut with_cabin = pick(0, &[]);
+        with_cabin
+            .as_mut()
+            .expect("Should be some.")
+            .ancillaries
+            .insert(0);
+
+        BlockDivisionState::set_selections_for_current_round(
+            &mut store,
+            id.to_string(),
+            PARTICIPANT_A.0,
+            Vec::from([with_cabin.clone()]),
+        )
+        .expect("A is eligible.");
+        assert!(BlockDivisionState::set_selections_for_current_round(
+            &mut store,
+            id.to_string(),
+            PARTICIPANT_B.0,
+            Vec::from([with_cabin]),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn serial_dictatorship_uses_one_order() {
+        let basis = create_basis_with_slots(1).with_allocation(Allocation::SerialDictatorship);
+        let mut bds = BlockDivisionState::new(&basis).expect("Should create.");
+        for round in 0..basis.get_selection_rounds().len() {
+            let drawn: Vec<Ranks> = bds
+                .bucket_states
+                .iter()
+                .map(|bucket_states| {
+                    bucket_states
+                        .get_state(&round)
+                        .ranks
+                        .clone()
+                        .expect("Should be drawn.")
+                })
+                .collect();
+            assert!(drawn.len() == BUCKET_INDICES.len());
+            assert!(drawn[0].len() == basis.get_participant_definitions().len());
+            assert!(drawn.iter().all(|ranks| *ranks == drawn[0]));
+        }
+        assert!(bds.verify_ranks().expect("Should verify.").matches);
+
+        //Whoever is first in the order gets their first choice, wherever they ask
+        let round = ROUND_1.0;
+        for bucket in 0..2 {
+            set_ranks(&mut bds, bucket, round, [2, 1, 3]);
+        }
+        bds.selections
+            .set(round, PARTICIPANT_A.0, Vec::from([pick(0, &[1])]));
+        bds.selections
+            .set(round, PARTICIPANT_B.0, Vec::from([pick(1, &[0])]));
+        bds.determine_designations_from_current_selections();
+
+        assert!(get_pick(&bds, round, PARTICIPANT_A.0).granted_option == Some(0));
+        assert!(get_pick(&bds, round, PARTICIPANT_B.0).granted_option == Some(0));
+    }
+
+    #[test]
+    fn undrawable_lottery_is_an_error() {
+        let mut lottery = LotteryRecord::random();
+        lottery.censor();
+        assert!(BlockDivisionState::from_lottery(&create_basis(), lottery.clone()).is_err());
+
+        let at = Utc::now();
+        let created = DivisionEvent::Created {
+            basis: create_basis(),
+            lottery: lottery,
+        };
+        assert!(history::replay(&Vec::from([EventRecord::create(at, created)]), &at).is_err());
+    }
+
+    #[test]
+    fn weighted_lottery_records_weights() {
+        let basis = create_basis().with_weighted_lottery(true);
+        let bds = BlockDivisionState::new(&basis).expect("Should create.");
+
+        let weights = Some(Vec::from([1, 1, 1]));
+        assert!(
+            *bds.get_lottery()
+                .as_ref()
+                .expect("Should be some.")
+                .get_weights()
+                == weights
+        );
+        let verification = bds.verify_ranks().expect("Should verify.");
+        assert!(verification.matches);
+        assert!(verification.weights == weights);
+
+        let unweighted = BlockDivisionState::new(&create_basis()).expect("Should create.");
+        assert!(unweighted
+            .get_lottery()
+            .as_ref()
+            .expect("Should be some.")
+            .get_weights()
+            .is_none());
+    }
+
+    #[test]
+    fn added_participants_draw_with_their_weight() {
+        let mut bds = BlockDivisionState::new(&create_basis().with_weighted_lottery(true))
+            .expect("Should create.");
+        let late_hire = ParticipantDef::create(
+            "Participant D".to_string(),
+            "testing_d@autoscheda.com".to_string(),
+            Vec::from([PICKS_PER_ROUND; 4]),
+        )
+        .with_weight(50);
+        amend(&mut bds, BasisAmendment::AddParticipant(late_hire)).expect("Should add.");
+        let late_hire = 3;
+
+        let weights = Some(Vec::from([1, 1, 1, 50]));
+        let recorded = |bds: &BlockDivisionState| {
+            bds.amendments
+                .last()
+                .and_then(|amendment| amendment.lottery.as_ref())
+                .expect("Should be some.")
+                .get_weights()
+                .clone()
+        };
+        assert!(recorded(&bds) == weights);
+
+        //50 in 53 odds of the best rank in each of the 24 draws
+        let firsts = bds
+            .bucket_states
+            .iter()
+            .flat_map(|bucket_state| bucket_state.get_states())
+            .filter(|round_state| round_state.get_rank(&late_hire) == &1)
+            .count();
+        assert!(firsts >= 18);
+
+        //A bucket added later ranks them with the same weight
+        let mut new_bucket = bds.basis.get_bucket_definitions()[0].clone();
+        new_bucket.name = "Bucket New".to_string();
+        amend(&mut bds, BasisAmendment::AddBucket(new_bucket)).expect("Should add.");
+        assert!(recorded(&bds) == weights);
+        assert!(bds.verify_ranks().expect("Should verify.").matches);
+    }
+
+    #[test]
+    fn overrides_survive_recompute() {
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        let round = ROUND_1.0;
+        set_ranks(&mut bds, 0, round, [1, 2, 3]);
+        bds.selections
+            .set(round, PARTICIPANT_A.0, Vec::from([pick(0, &[])]));
+        bds.determine_designations_from_current_selections();
+        assert!(*get_designations(&bds, 0, round) == Designations::from([PARTICIPANT_A.0]));
+
+        //C takes the only slot and the ancillary despite the worst rank, and keeps them through every recompute
+        bds.assign_override(
+            round,
+            0,
+            PARTICIPANT_C.0,
+            BTreeSet::from([BLACK_BUTTE.0]),
+            Utc::now(),
+        )
+        .expect("Should override.");
+        bds.determine_designations_from_current_selections();
+        let round_state = bds.bucket_states[0].get_state(&round);
+        assert!(round_state.designations == Designations::from([PARTICIPANT_C.0]));
+        assert!(
+            round_state.ancillary_designations[&BLACK_BUTTE.0] == BTreeSet::from([PARTICIPANT_C.0])
+        );
+        assert!(round_state.overrides.contains_key(&PARTICIPANT_C.0));
+        assert!(
+            get_pick(&bds, round, PARTICIPANT_A.0).state
+                == Some(SelectionResult::RejectedNoSelectionsThisRound)
+        );
+
+        //Once finalized, removing the override promotes A from the waitlist
+        bds.finalize_round(round).expect("Should finalize.");
+        let promotions = bds
+            .clear_override(round, 0, PARTICIPANT_C.0, Utc::now())
+            .expect("Should remove.");
+        assert!(promotions.len() == 1 && promotions[0].participant == PARTICIPANT_A.0);
+        assert!(*get_designations(&bds, 0, round) == Designations::from([PARTICIPANT_A.0]));
+        assert!(bds
+            .clear_override(round, 0, PARTICIPANT_C.0, Utc::now())
+            .is_err());
+        assert!(
+            bds.override_log
+                .iter()
+                .map(|record| record.assigned)
+                .collect::<Vec<bool>>()
+                == Vec::from([true, false])
+        );
+    }
+
+    #[test]
+    fn explanations_name_who_won() {
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        let round = ROUND_1.0;
+        set_ranks(&mut bds, 0, round, [1, 2, 3]);
+        set_ranks(&mut bds, 1, round, [3, 2, 1]);
+        bds.selections
+            .set(round, PARTICIPANT_A.0, Vec::from([pick(0, &[])]));
+        bds.selections
+            .set(round, PARTICIPANT_B.0, Vec::from([pick(0, &[1])]));
+        bds.selections
+            .set(round, PARTICIPANT_C.0, Vec::from([pick(1, &[])]));
+        bds.determine_designations_from_current_selections();
+
+        let explanations = &get_pick(&bds, round, PARTICIPANT_A.0).explanations;
+        assert!(explanations[&0] == Explanation::Granted { rank: Some(1) });
+
+        //B lost their first choice to A and their fallback to C, who was settled first
+        let explanations = &get_pick(&bds, round, PARTICIPANT_B.0).explanations;
+        assert!(
+            explanations[&0]
+                == Explanation::Outranked {
+                    rank: Some(2),
+                    holders: Vec::from([Rival {
+                        participant: PARTICIPANT_A.0,
+                        rank: Some(1)
+                    }])
+                }
+        );
+        assert!(
+            explanations[&1]
+                == Explanation::Outranked {
+                    rank: Some(2),
+                    holders: Vec::from([Rival {
+                        participant: PARTICIPANT_C.0,
+                        rank: Some(1)
+                    }])
+                }
+        );
+
+        bds.censor_explanations(&round);
+        let explanations = &get_pick(&bds, round, PARTICIPANT_B.0).explanations;
+        assert!(
+            explanations[&0]
+                == Explanation::Outranked {
+                    rank: None,
+                    holders: Vec::from([Rival {
+                        participant: PARTICIPANT_A.0,
+                        rank: None
+                    }])
+                }
+        );
+    }
+
+    #[test]
+    fn censored_explanations_hide_holder_order() {
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(2)).expect("Should create.");
+        let round = ROUND_1.0;
+        set_ranks(&mut bds, 0, round, [3, 2, 1]);
+        for participant in [PARTICIPANT_A.0, PARTICIPANT_B.0, PARTICIPANT_C.0] {
+            bds.selections
+                .set(round, participant, Vec::from([pick(0, &[])]));
+        }
+        bds.determine_designations_from_current_selections();
+
+        let rivals = |participants: [usize; 2], ranks: [Option<usize>; 2]| {
+            Vec::from([
+                Rival {
+                    participant: participants[0],
+                    rank: ranks[0],
+                },
+                Rival {
+                    participant: participants[1],
+                    rank: ranks[1],
+                },
+            ])
+        };
+        assert!(
+            get_pick(&bds, round, PARTICIPANT_A.0).explanations[&0]
+                == Explanation::Outranked {
+                    rank: Some(3),
+                    holders: rivals([PARTICIPANT_C.0, PARTICIPANT_B.0], [Some(1), Some(2)])
+                }
+        );
+
+        //Best first would still say C outranks B
+        bds.censor_explanations(&round);
+        assert!(
+            get_pick(&bds, round, PARTICIPANT_A.0).explanations[&0]
+                == Explanation::Outranked {
+                    rank: None,
+                    holders: rivals([PARTICIPANT_B.0, PARTICIPANT_C.0], [None, None])
+                }
+        );
+    }
+
+    #[test]
+    fn removal_promotes_from_waitlist_in_finalized_round() {
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        let round = ROUND_1.0;
+        set_ranks(&mut bds, 0, round, [1, 2, 3]);
+        set_ranks(&mut bds, 1, round, [1, 2, 3]);
+
+        //B falls back to bucket 1, but would rather wait for bucket 0
+        bds.selections
+            .set(round, PARTICIPANT_A.0, Vec::from([pick(0, &[])]));
+        bds.selections
+            .set(round, PARTICIPANT_B.0, Vec::from([pick(0, &[1])]));
+        bds.selections
+            .set(round, PARTICIPANT_C.0, Vec::from([pick(0, &[])]));
+        bds.finalize_round(round).expect("Should finalize.");
+
+        let waitlist = &bds.bucket_states[0].get_state(&round).waitlist;
+        assert!(
+            waitlist
+                .iter()
+                .map(|entry| entry.participant)
+                .collect::<Vec<_>>()
+                == Vec::from([PARTICIPANT_B.0, PARTICIPANT_C.0])
+        );
+        assert!(*get_designations(&bds, 1, round) == Designations::from([PARTICIPANT_B.0]));
+
+        let promotions = bds
+            .release_designation(
+                round,
+                0,
+                PARTICIPANT_A.0,
+                Release::create(Releaser::Admin, Utc::now()),
+            )
+            .expect("Should release.");
+        assert!(
+            promotions
+                == Vec::from([Promotion {
+                    round: round,
+                    bucket_index: 0,
+                    participant: PARTICIPANT_B.0,
+                    notified: false,
+                }])
+        );
+        assert!(*get_designations(&bds, 0, round) == Designations::from([PARTICIPANT_B.0]));
+        assert!(get_designations(&bds, 1, round).is_empty()); //B gave up their fallback
+        assert!(get_pick(&bds, round, PARTICIPANT_A.0).state == Some(SelectionResult::Released));
+        let pick_b = get_pick(&bds, round, PARTICIPANT_B.0);
+        assert!(pick_b.state == Some(SelectionResult::Confirmed));
+        assert!(pick_b.granted_option == Some(0));
+
+        //C is still waiting, and A can't be removed twice
+        let waitlist = &bds.bucket_states[0].get_state(&round).waitlist;
+        assert!(waitlist.len() == 1 && waitlist[0].participant == PARTICIPANT_C.0);
+        assert!(bds
+            .release_designation(
+                round,
+                0,
+                PARTICIPANT_A.0,
+                Release::create(Releaser::Admin, Utc::now())
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn removal_in_open_round_recomputes() {
+        let mut store = InMemoryDivisionStore::new();
+        let id = "Test Block Division Removal";
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        set_ranks(&mut bds, 0, ROUND_1.0, [1, 2, 3]);
+        store.insert_state(id, &bds).expect("Should insert.");
+
+        BlockDivisionState::set_open_round(&mut store, id.to_string(), Some(ROUND_1.0))
+            .expect("Couldn't set open round.");
+        for participant in [PARTICIPANT_A.0, PARTICIPANT_B.0] {
+            BlockDivisionState::set_selections_for_current_round(
+                &mut store,
+                id.to_string(),
+                participant,
+                Vec::from([pick(0, &[])]),
+            )
+            .expect("Should be able to input selection.");
+        }
+
+        let promotions = BlockDivisionState::withdraw(
+            &mut store,
+            id.to_string(),
+            ROUND_1.0,
+            0,
+            PARTICIPANT_A.0,
+            Release::create(Releaser::Admin, Utc::now()),
+        )
+        .expect("Should remove.");
+        assert!(promotions.len() == 1 && promotions[0].participant == PARTICIPANT_B.0);
+
+        //Resubmitting doesn't put A back
+        BlockDivisionState::set_selections_for_current_round(
+            &mut store,
+            id.to_string(),
+            PARTICIPANT_A.0,
+            Vec::from([pick(0, &[])]),
+        )
+        .expect("Should be able to input selection.");
+        let bds = store
+            .get_state(id)
+            .expect("Should be a state.")
+            .expect("Should exist.");
+        assert!(*get_designations(&bds, 0, ROUND_1.0) == Designations::from([PARTICIPANT_B.0]));
+        assert!(
+            get_pick(&bds, ROUND_1.0, PARTICIPANT_A.0).state == Some(SelectionResult::Released)
+        );
+        assert!(
+            bds.bucket_states[0].get_state(&ROUND_1.0).released[&PARTICIPANT_A.0].by
+                == Releaser::Admin
+        );
+    }
+
+    #[test]
+    fn withdrawal_policy_guards_finalized_rounds() {
+        let mut store = InMemoryDivisionStore::new();
+        let id = "Test Block Division Withdrawal";
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        set_ranks(&mut bds, 0, ROUND_1.0, [1, 2, 3]);
+        bds.selections
+            .set(ROUND_1.0, PARTICIPANT_A.0, Vec::from([pick(0, &[])]));
+        bds.selections
+            .set(ROUND_1.0, PARTICIPANT_B.0, Vec::from([pick(0, &[])]));
+        bds.finalize_round(ROUND_1.0).expect("Should finalize.");
+        store.insert_state(id, &bds).expect("Should insert.");
 
-                    let rank_index = rng.gen_range(0..available_ranks_as_vec.len());
-                    let rank = available_ranks_as_vec
-                        .get(rank_index)
-                        .expect("Should exist.");
+        let at = Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 0).unwrap();
+        let withdraw = |store: &mut InMemoryDivisionStore, by: Releaser| {
+            BlockDivisionState::withdraw(
+                store,
+                id.to_string(),
+                ROUND_1.0,
+                0,
+                PARTICIPANT_A.0,
+                Release::create(by, at),
+            )
+        };
 
-                    bucket_state_this_round.insert(participant.to_owned(), rank.to_owned());
+        //Admins only by default
+        assert!(withdraw(&mut store, Releaser::Participant).is_err());
+        BlockDivisionState::set_withdrawal_policy(
+            &mut store,
+            id.to_string(),
+            WithdrawalPolicy::Frozen,
+        )
+        .expect("Should set.");
+        assert!(withdraw(&mut store, Releaser::Admin).is_err());
 
-                    /*
-                    println!(
-                        "Adding rank {} for participant {} for round {} in bucket {}",
-                        rank,
-                        participant.to_string(),
-                        round,
-                        bucket_name
-                    );
-                    println!("");
-                    */
-                }
+        BlockDivisionState::set_withdrawal_policy(
+            &mut store,
+            id.to_string(),
+            WithdrawalPolicy::Anyone,
+        )
+        .expect("Should set.");
+        let promotions = withdraw(&mut store, Releaser::Participant).expect("Should withdraw.");
+        assert!(promotions.len() == 1 && promotions[0].participant == PARTICIPANT_B.0);
 
-                bucket.get_state_mut(&round).ranks = Some(bucket_state_this_round);
-            }
-        }
+        let bds = store
+            .get_state(id)
+            .expect("Should be a state.")
+            .expect("Should exist.");
+        assert!(
+            bds.bucket_states[0].get_state(&ROUND_1.0).released[&PARTICIPANT_A.0]
+                == Release::create(Releaser::Participant, at)
+        );
+        assert!(bds.get_holdings(&PARTICIPANT_A.0).is_empty());
     }
 
-    pub(crate) fn pretty_print(&self) {
-        let serialized = serde_json::to_string_pretty(self).expect("Should serialize.");
-        println!("{}", serialized);
-    }
+    #[test]
+    fn removing_participant_ignores_frozen_withdrawal_policy() {
+        let mut store = InMemoryDivisionStore::new();
+        let id = "Test Block Division Frozen Removal";
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        set_ranks(&mut bds, 0, ROUND_1.0, [1, 2, 3]);
+        bds.selections
+            .set(ROUND_1.0, PARTICIPANT_A.0, Vec::from([pick(0, &[])]));
+        bds.selections
+            .set(ROUND_1.0, PARTICIPANT_B.0, Vec::from([pick(0, &[])]));
+        bds.finalize_round(ROUND_1.0).expect("Should finalize.");
+        store.insert_state(id, &bds).expect("Should insert.");
+        BlockDivisionState::set_withdrawal_policy(
+            &mut store,
+            id.to_string(),
+            WithdrawalPolicy::Frozen,
+        )
+        .expect("Should set.");
 
-    fn slots_available_this_round(&self, bucket_index: &usize, current_round: &usize) -> usize {
-        let mut used_slots: usize = 0;
+        let at = Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 0).unwrap();
+        let promotions = BlockDivisionState::amend(
+            &mut store,
+            id.to_string(),
+            BasisAmendment::RemoveParticipant(PARTICIPANT_A.0),
+            at,
+        )
+        .expect("Should remove.");
+        assert!(promotions.len() == 1 && promotions[0].participant == PARTICIPANT_B.0);
 
-        if current_round > &0 {
-            for previous_round in 0..current_round - 1 {
-                used_slots += self
-                    .bucket_states
-                    .get(*bucket_index)
-                    .expect("Bucket should exist.")
-                    .get_state(&previous_round)
-                    .designations
-                    .len() as usize;
-            }
+        let bds = store
+            .get_state(id)
+            .expect("Should be a state.")
+            .expect("Should exist.");
+        assert!(*get_designations(&bds, 0, ROUND_1.0) == Designations::from([PARTICIPANT_B.0]));
+        assert!(
+            bds.bucket_states[0].get_state(&ROUND_1.0).released[&PARTICIPANT_A.0]
+                == Release::create(Releaser::Admin, at)
+        );
+    }
+
+    #[test]
+    fn swaps_need_consent_and_keep_the_rules() {
+        let mut bds = BlockDivisionState::new(&create_basis()).expect("Should create.");
+        let round = ROUND_1.0;
+        for (bucket, participant) in [
+            (0, PARTICIPANT_A.0),
+            (2, PARTICIPANT_B.0),
+            (1, PARTICIPANT_B.0),
+            (OVERLAPPING_BUCKET, PARTICIPANT_C.0),
+        ] {
+            bds.bucket_states[bucket]
+                .get_state_mut(&round)
+                .designations
+                .insert(participant);
         }
+        bds.bucket_states[0]
+            .get_state_mut(&round)
+            .ancillary_designations
+            .insert(BLACK_BUTTE.0, BTreeSet::from([PARTICIPANT_A.0]));
+        let at = Utc.with_ymd_and_hms(2025, 4, 1, 12, 0, 0).unwrap();
 
-        let available_slots = self
-            .basis
-            .get_bucket_definitions()
-            .get(*bucket_index)
-            .expect("Bucket should exist.")
-            .available_slots;
+        //Open rounds would be recomputed, so they can't be swapped
+        let a_gives = Holding::create(round, 0);
+        let b_gives = Holding::create(round, 2);
+        assert!(bds
+            .add_swap_proposal(PARTICIPANT_A.0, a_gives, PARTICIPANT_B.0, b_gives, at)
+            .is_err());
+        bds.finalized_rounds.insert(round);
 
-        available_slots - used_slots
-    }
+        let swap = bds
+            .add_swap_proposal(PARTICIPANT_A.0, a_gives, PARTICIPANT_B.0, b_gives, at)
+            .expect("Should propose.");
+        assert!(bds.resolve_swap(PARTICIPANT_C.0, swap, true, at).is_err());
+        assert!(bds.resolve_swap(PARTICIPANT_A.0, swap, true, at).is_err()); //Only the recipient accepts
+        assert!(
+            bds.resolve_swap(PARTICIPANT_B.0, swap, true, at)
+                .expect("Should accept.")
+                == SwapStatus::Accepted
+        );
 
-    pub fn attempt_selection(
-        &mut self,
-        round: &usize,
-        participant: &ParticipantIndex,
-        selection: &Selection,
-    ) -> SelectionResult {
-        let winners = {
-            let bucket_states = self
-                .bucket_states
-                .get(selection.bucket_index)
-                .expect("Bucket should exist.");
-            let round_state = bucket_states.get_state(&round);
+        assert!(bds.get_holdings(&PARTICIPANT_A.0) == BTreeMap::from([(2, BTreeSet::new())]));
+        assert!(
+            bds.get_holdings(&PARTICIPANT_B.0)
+                == BTreeMap::from([(0, BTreeSet::from([BLACK_BUTTE.0])), (1, BTreeSet::new())])
+        );
+        assert!(bds.get_swaps()[swap].resolved_at == Some(at));
+        assert!(bds.resolve_swap(PARTICIPANT_B.0, swap, true, at).is_err());
 
-            //Check ancillary designations first. If this participant loses any, reject the selection
-            let mut unavailable_ancillaries: Vec<usize> = Vec::new();
-            for ancillary_designation in &selection.ancillaries {
-                if !bucket_states.ancillary_designation_is_available_for_this_round(
-                    &round,
-                    &ancillary_designation,
-                ) {
-                    //Can't get ancillary, so selection is denied
-                    unavailable_ancillaries.push(*ancillary_designation);
-                }
+        //B can't take C's bucket, which overlaps the ones B holds. Nothing changes.
+        let before = bds.bucket_states.clone();
+        let overlapping = bds
+            .add_swap_proposal(
+                PARTICIPANT_C.0,
+                Holding::create(round, OVERLAPPING_BUCKET),
+                PARTICIPANT_B.0,
+                Holding::create(round, 1),
+                at,
+            )
+            .expect("Should propose.");
+        assert!(bds
+            .resolve_swap(PARTICIPANT_B.0, overlapping, true, at)
+            .is_err());
+        assert!(bds.bucket_states == before);
+        assert!(bds.get_swaps()[overlapping].get_status() == SwapStatus::Proposed);
+        assert!(
+            bds.resolve_swap(PARTICIPANT_C.0, overlapping, false, at)
+                .expect("Should cancel.")
+                == SwapStatus::Cancelled
+        );
+    }
 
-                match round_state
-                    .ancillary_designations
-                    .get(ancillary_designation)
-                {
-                    Some(current_ancillary_designee) => {
-                        if !round_state.is_winner(participant, current_ancillary_designee) {
-                            //Can't get ancillary, so selection is denied
-                            println!(
-                                "{} beat {} for ancillary {}",
-                                current_ancillary_designee, participant, ancillary_designation
-                            );
-                            unavailable_ancillaries.push(*ancillary_designation);
-                        }
-                    }
-                    None => {}
-                }
-            }
-            if unavailable_ancillaries.len() > 0 {
-                return SelectionResult::RejectedAncillaryUnavailable(unavailable_ancillaries);
-            }
+    #[test]
+    fn swap_updates_picks_and_later_rounds() {
+        let mut store = InMemoryDivisionStore::new();
+        let id = "Swapped";
+        let bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        store.insert_state(id, &bds).expect("Should insert.");
+        let at = Utc.with_ymd_and_hms(2025, 4, 1, 12, 0, 0).unwrap();
 
-            let slots_available = self.slots_available_this_round(&selection.bucket_index, round);
-            if slots_available <= 0 {
-                return SelectionResult::RejectedNoSelectionsThisRound;
-            }
+        BlockDivisionState::set_open_round(&mut store, id.to_string(), Some(ROUND_1.0))
+            .expect("Couldn't set open round.");
+        for (participant, bucket) in [(PARTICIPANT_A.0, 0), (PARTICIPANT_B.0, 2)] {
+            BlockDivisionState::set_selections_for_current_round(
+                &mut store,
+                id.to_string(),
+                participant,
+                Vec::from([pick(bucket, &[])]),
+            )
+            .expect("Should be able to input selection.");
+        }
+        BlockDivisionState::close_round(&mut store, id.to_string(), ROUND_1.0)
+            .expect("Should close.");
 
-            let mut candidates: BTreeSet<ParticipantIndex> =
-                round_state.designations.clone().into_iter().collect();
-            candidates.insert(*participant);
+        //The overlapping bucket conflicts with bucket 0, which A holds until the swap
+        BlockDivisionState::set_open_round(&mut store, id.to_string(), Some(ROUND_2.0))
+            .expect("Couldn't set open round.");
+        BlockDivisionState::set_selections_for_current_round(
+            &mut store,
+            id.to_string(),
+            PARTICIPANT_A.0,
+            Vec::from([pick(OVERLAPPING_BUCKET, &[])]),
+        )
+        .expect("Should be able to input selection.");
+        let bds = store
+            .get_state(id)
+            .expect("Should be a state.")
+            .expect("Should exist.");
+        assert!(
+            get_pick(&bds, ROUND_2.0, PARTICIPANT_A.0).state
+                == Some(SelectionResult::RejectedCalendarConflict(Vec::from([0])))
+        );
 
-            round_state.get_winners(&candidates, slots_available)
-        };
+        let swap = BlockDivisionState::propose_swap(
+            &mut store,
+            id.to_string(),
+            PARTICIPANT_A.0,
+            Holding::create(ROUND_1.0, 0),
+            PARTICIPANT_B.0,
+            Holding::create(ROUND_1.0, 2),
+            at,
+        )
+        .expect("Should propose.");
+        BlockDivisionState::respond_to_swap(
+            &mut store,
+            id.to_string(),
+            PARTICIPANT_B.0,
+            swap,
+            true,
+            at,
+        )
+        .expect("Should accept.");
 
-        if winners.contains(participant) {
-            //Update ancillaries and designations
-            let bucket_states_mut = self
-                .bucket_states
-                .get_mut(selection.bucket_index)
-                .expect("Bucket should exist.");
-            let round_state_mut = bucket_states_mut.get_state_mut(&round);
+        let bds = store
+            .get_state(id)
+            .expect("Should be a state.")
+            .expect("Should exist.");
+        assert!(
+            bds.get_holdings(&PARTICIPANT_A.0)
+                == BTreeMap::from([(2, BTreeSet::new()), (OVERLAPPING_BUCKET, BTreeSet::new())])
+        );
+        assert!(bds.get_holdings(&PARTICIPANT_B.0) == BTreeMap::from([(0, BTreeSet::new())]));
 
-            round_state_mut.designations = winners;
+        //The pick given up is marked in place, so each round keeps the picks it allows. What was received is in the holdings.
+        for (participant, given) in [(PARTICIPANT_A.0, 0), (PARTICIPANT_B.0, 2)] {
+            let given_pick = get_nth_pick(&bds, ROUND_1.0, participant, 0);
+            assert!(given_pick.bucket_index == given);
+            assert!(given_pick.state == Some(SelectionResult::SwappedAway));
+            assert!(given_pick.granted_option.is_none());
+            assert!(given_pick.explanations[&0] == Explanation::SwappedAway { swap_index: swap });
+            assert!(
+                bds.selections.get(&ROUND_1.0).expect("Should exist.")[&participant].len()
+                    == PICKS_PER_ROUND
+            );
+        }
 
-            for ancillary_designation in &selection.ancillaries {
-                round_state_mut
-                    .ancillary_designations
-                    .insert(*ancillary_designation, *participant);
-            }
+        //The open round was recomputed against A's new holding
+        assert!(
+            get_pick(&bds, ROUND_2.0, PARTICIPANT_A.0).state == Some(SelectionResult::Confirmed)
+        );
 
-            SelectionResult::Confirmed
-        } else {
-            SelectionResult::RejectedOutranked
-        }
+        BlockDivisionState::close_round(&mut store, id.to_string(), ROUND_2.0)
+            .expect("Should close.");
+        let bds = store
+            .get_state(id)
+            .expect("Should be a state.")
+            .expect("Should exist.");
+        let entries = ledger::entries_from_state(id, &bds);
+        //A swap trades a win for a win, and the holiday bucket goes with it
+        assert!(
+            entries[PARTICIPANT_A.0].wins == 2 && entries[PARTICIPANT_A.0].holiday_buckets == 0
+        );
+        assert!(
+            entries[PARTICIPANT_B.0].wins == 1 && entries[PARTICIPANT_B.0].holiday_buckets == 1
+        );
     }
 
-    pub fn set_open_round(
-        conn: &mut PgConnection,
-        state_id: String,
-        round_index: Option<usize>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        match PersistentDivision::get_from_id(conn, &state_id) {
-            Some(pd) => match pd.as_state() {
-                Ok(mut state) => {
-                    match round_index {
-                        Some(round_index) => {
-                            let mut contains_key = false;
-                            for key in 0..state.basis.get_selection_rounds().len() {
-                                if key == round_index {
-                                    state.current_open_round = Some(round_index);
-                                    contains_key = true;
-                                    break;
-                                }
-                            }
-                            if !contains_key {
-                                return Err(Box::new(std::io::Error::new(
-                                    std::io::ErrorKind::InvalidInput,
-                                    format!("Invalid round index {}.", round_index),
-                                )));
-                            }
-                        }
-                        None => {}
-                    };
+    #[test]
+    fn simulation_projects_without_saving() {
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        set_ranks(&mut bds, 0, ROUND_1.0, [2, 1, 3]);
+        bds.open_round(Some(ROUND_1.0)).expect("Should open.");
+        bds.apply_selections(PARTICIPANT_B.0, Vec::from([pick(0, &[])]))
+            .expect("Should apply.");
+        let before = bds.clone();
 
-                    state.current_open_round = round_index;
-                    state.save_state(state_id, conn)
+        let projected = bds
+            .simulate_selections(PARTICIPANT_A.0, Vec::from([pick(0, &[1])]))
+            .expect("Should simulate.");
+        let projected = projected[0].as_ref().expect("Should be some.");
+        assert!(projected.state == Some(SelectionResult::Confirmed));
+        assert!(projected.granted_option == Some(1)); //B outranks A for bucket 0
+        assert!(
+            projected.explanations[&0]
+                == Explanation::Outranked {
+                    rank: None,
+                    holders: Vec::from([Rival {
+                        participant: PARTICIPANT_B.0,
+                        rank: None
+                    }])
                 }
-                Err(e) => Err(e),
-            },
-            None => Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                format!("No state with id {}", state_id),
-            ))),
-        }
-    }
-}
+        );
+        assert!(projected.explanations[&1] == Explanation::Granted { rank: None });
+        assert!(bds == before);
 
-#[cfg(test)]
-mod tests {
-    use bucket::AncillaryIndex;
+        assert!(bds
+            .simulate_selections(PARTICIPANT_A.0, Vec::from([pick(0, &[]), pick(1, &[])]))
+            .is_err());
+        //Checked like a submission, so there's no projecting for a participant who doesn't exist
+        assert!(bds
+            .simulate_selections(PARTICIPANT_C.0 + 1, Vec::from([pick(0, &[])]))
+            .is_err());
+    }
 
-    use crate::db::{division::PersistentDivision, establish_connection};
+    #[test]
+    fn completed_division_goes_in_ledger() {
+        let mut store = InMemoryDivisionStore::new();
+        let id = "Last Year";
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        set_ranks(&mut bds, 0, ROUND_1.0, [1, 2, 3]);
+        store.insert_state(id, &bds).expect("Should insert.");
 
-    use super::*;
+        BlockDivisionState::set_open_round(&mut store, id.to_string(), Some(ROUND_1.0))
+            .expect("Couldn't set open round.");
+        for participant in [PARTICIPANT_A.0, PARTICIPANT_B.0] {
+            BlockDivisionState::set_selections_for_current_round(
+                &mut store,
+                id.to_string(),
+                participant,
+                Vec::from([pick(0, &[])]),
+            )
+            .expect("Should be able to input selection.");
+        }
 
-    const PARTICIPANT_A: (usize, &str, &str) = (0, "Participant A", "testing_a@autoscheda.com");
-    const PARTICIPANT_B: (usize, &str, &str) = (1, "Participant B", "testing_b@autoscheda.com");
-    const PARTICIPANT_C: (usize, &str, &str) = (2, "Participant C", "testing_c@autoscheda.com");
-    const BLACK_BUTTE: (usize, &str) = (0, "Black Butte");
-    const BUCKET_INDICES: [usize; 6] = [0, 1, 2, 3, 4, 5];
+        //Not complete until the last round closes
+        assert!(BlockDivisionState::record_in_ledger(&mut store, id.to_string()).is_err());
+        BlockDivisionState::close_round(&mut store, id.to_string(), ROUND_1.0)
+            .expect("Should close.");
+        assert!(store.get_ledger(None).expect("Should work.").is_empty());
+        BlockDivisionState::close_round(&mut store, id.to_string(), ROUND_4.0)
+            .expect("Should close.");
 
-    const ROUND_1: (usize, &str) = (0, "Predesignation");
-    const ROUND_2: (usize, &str) = (1, "Round 1");
-    const ROUND_3: (usize, &str) = (2, "Round 2");
-    const ROUND_4: (usize, &str) = (3, "Round 3");
+        let ledger = store.get_ledger(None).expect("Should work.");
+        assert!(ledger.len() == 3);
+        let a = &ledger[PARTICIPANT_A.2][0];
+        assert!((a.wins, a.losses, a.holiday_buckets) == (1, 0, 1));
+        let b = &ledger[PARTICIPANT_B.2][0];
+        assert!((b.wins, b.losses, b.holiday_buckets) == (0, 1, 0));
 
-    const PICKS_PER_ROUND: usize = 1;
+        //Recording again replaces the division's entries instead of adding to them
+        BlockDivisionState::record_in_ledger(&mut store, id.to_string()).expect("Should record.");
+        let only_b = store
+            .get_ledger(Some(PARTICIPANT_B.2))
+            .expect("Should work.");
+        assert!(only_b.len() == 1 && only_b[PARTICIPANT_B.2].len() == 1);
 
-    fn bucketname(i: usize) -> String {
-        "Bucket ".to_string() + &i.to_string()
+        //B lost and missed the holiday, C only missed the holiday
+        let next = store
+            .new_division("This Year", &create_basis().with_ledger_weighted(true))
+            .expect("Should work.");
+        let weights = next
+            .get_lottery()
+            .as_ref()
+            .expect("Should be some.")
+            .get_weights();
+        assert!(*weights == Some(Vec::from([1, 3, 2])));
     }
 
-    fn create_basis() -> BlockDivisionBasis {
-        let mut buckets: Vec<BucketDef> = Vec::new();
+    #[test]
+    fn finalized_rounds_are_frozen() {
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        set_ranks(&mut bds, 0, ROUND_1.0, [2, 1, 3]);
 
-        for n in BUCKET_INDICES {
-            buckets.insert(
-                n,
-                BucketDef {
-                    name: bucketname(n),
-                    available_slots: 5,
-                    available_ancillaries: Vec::from([(BLACK_BUTTE.1.to_string())]),
-                },
-            );
-        }
+        bds.open_round(Some(ROUND_1.0)).expect("Should open.");
+        bds.selections
+            .set(ROUND_1.0, PARTICIPANT_A.0, Vec::from([pick(0, &[])]));
+        bds.determine_designations_from_current_selections();
+        assert!(*get_designations(&bds, 0, ROUND_1.0) == Designations::from([PARTICIPANT_A.0]));
 
-        let rounds: Vec<RoundName> = Vec::from([
-            ROUND_1.1.to_string(),
-            ROUND_2.1.to_string(),
-            ROUND_3.1.to_string(),
-            ROUND_4.1.to_string(),
-        ]);
+        bds.open_round(Some(ROUND_2.0)).expect("Should open.");
+        assert!(bds.is_finalized(&ROUND_1.0));
+        assert!(!bds.is_finalized(&ROUND_2.0));
+        assert!(bds.open_round(Some(ROUND_1.0)).is_err());
 
-        let mut round_picks: Vec<usize> = Vec::new();
-        for n in 0..rounds.len() {
-            round_picks.insert(n, PICKS_PER_ROUND);
-        }
+        //B outranks A in round 1, but round 1 is over, so nothing B does can change it
+        bds.selections
+            .set(ROUND_1.0, PARTICIPANT_B.0, Vec::from([pick(0, &[])]));
+        bds.selections
+            .set(ROUND_2.0, PARTICIPANT_B.0, Vec::from([pick(0, &[])]));
+        bds.determine_designations_from_current_selections();
 
-        let mut participants: Vec<ParticipantDef> = Vec::new();
+        assert!(*get_designations(&bds, 0, ROUND_1.0) == Designations::from([PARTICIPANT_A.0]));
+        assert!(
+            get_pick(&bds, ROUND_1.0, PARTICIPANT_A.0).state == Some(SelectionResult::Confirmed)
+        );
+        assert!(get_pick(&bds, ROUND_1.0, PARTICIPANT_B.0).state.is_none());
 
-        for participant in [PARTICIPANT_A, PARTICIPANT_B, PARTICIPANT_C] {
-            participants.insert(
-                participant.0,
-                ParticipantDef::create(
-                    participant.1.to_string(),
-                    participant.2.to_string(),
-                    round_picks.clone(),
-                ),
-            );
-        }
+        //And the only slot was used in round 1
+        assert!(
+            get_pick(&bds, ROUND_2.0, PARTICIPANT_B.0).state
+                == Some(SelectionResult::RejectedNoSelectionsThisRound)
+        );
+        assert!(get_designations(&bds, 0, ROUND_2.0).is_empty());
+    }
 
-        BlockDivisionBasis::create(buckets, participants, rounds)
+    #[test]
+    fn closed_round_rejects_selections() {
+        let mut store = InMemoryDivisionStore::new();
+        let id = "Test Block Division Close";
+        store
+            .new_division(id, &create_basis())
+            .expect("Should work.");
+
+        BlockDivisionState::set_open_round(&mut store, id.to_string(), Some(ROUND_1.0))
+            .expect("Couldn't set open round.");
+        BlockDivisionState::set_selections_for_current_round(
+            &mut store,
+            id.to_string(),
+            PARTICIPANT_A.0,
+            Vec::from([pick(0, &[])]),
+        )
+        .expect("Should be able to input selection.");
+
+        BlockDivisionState::close_round(&mut store, id.to_string(), ROUND_1.0)
+            .expect("Should close.");
+        let bds = store
+            .get_state(id)
+            .expect("Should be a state.")
+            .expect("Should exist.");
+        assert!(bds.is_finalized(&ROUND_1.0));
+        assert!(bds.current_open_round.is_none());
+
+        assert!(BlockDivisionState::set_selections_for_current_round(
+            &mut store,
+            id.to_string(),
+            PARTICIPANT_A.0,
+            Vec::from([pick(1, &[])]),
+        )
+        .is_err());
+        assert!(
+            BlockDivisionState::set_open_round(&mut store, id.to_string(), Some(ROUND_1.0))
+                .is_err()
+        );
+        assert!(BlockDivisionState::close_round(&mut store, id.to_string(), 99).is_err());
     }
 
     #[test]
     fn block_division_cache_and_serialization_testing() {
-        dotenvy::dotenv().expect("Couldn't load environment variables for testing.");
-        let mut conn = establish_connection();
+        let mut store = InMemoryDivisionStore::new();
         let basis = create_basis();
 
         let id1 = "Test Block Division 1";
         let id2 = "Test Block Division 2";
 
-        match PersistentDivision::delete_division(&mut conn, id1.to_string()) {
-            Ok(_) => {}
-            Err(e) => {
-                eprintln!("Couldn't delete pre-existing pd, but this may not be an error.");
-            }
-        }
-
-        match PersistentDivision::delete_division(&mut conn, id2.to_string()) {
-            Ok(_) => {}
-            Err(e) => {
-                eprintln!("Couldn't delete pre-existing pd, but this may not be an error.");
-            }
-        }
-
-        let pd = PersistentDivision::new(&mut conn, id1.to_string(), &basis) //create to test overwriting
-            .expect("Should work.");
-        let delete_count =
-            PersistentDivision::delete_division(&mut conn, pd.get_id()).expect("Should work"); //Delete just created, should have a result
+        store.new_division(id1, &basis).expect("Should work."); //create to test overwriting
+        let delete_count = store.delete_state(id1).expect("Should work"); //Delete just created, should have a result
         assert!(delete_count == 1);
 
-        let pd = PersistentDivision::new(&mut conn, id1.to_string(), &basis) //recreate to test ignoring
-            .expect("Should work.");
-        let bds = pd.as_state().expect("Should be a state.");
+        store.new_division(id1, &basis).expect("Should work."); //recreate to test ignoring
+        assert!(store.new_division(id1, &basis).is_err()); //Ids are unique
+        let bds = store
+            .get_state(id1)
+            .expect("Should be a state.")
+            .expect("Should exist.");
 
         println!("----------------------");
         println!("Block Division State Serialization:");
@@ -567,30 +3979,167 @@ mod tests {
         println!("----------------------");
         println!("");
 
-        let pd2 = PersistentDivision::new(&mut conn, id2.to_string(), &basis) //recreate to test equivalence
-            .expect("Should work.");
-        let bds2 = pd2.as_state().expect("Should be a state.");
+        store.new_division(id2, &basis).expect("Should work."); //recreate to test equivalence
+        let bds2 = store
+            .get_state(id2)
+            .expect("Should be a state.")
+            .expect("Should exist.");
 
-        assert!(pd != pd2); //Should have different ids.
+        assert!(store.get_all_states().expect("Should work.").len() == 2);
+        assert!(bds.lottery != bds2.lottery); //Should have different seeds.
         assert!(bds.basis == bds2.basis); //But basis should be identical
         assert!(bds.current_open_round == bds2.current_open_round); //And current round as well
     }
 
     #[test]
-    fn selection_and_calculation() {
-        dotenvy::dotenv().expect("Couldn't load environment variables for testing.");
-        let mut conn = establish_connection();
+    fn rank_verification() {
         let basis = create_basis();
+        let mut bds = BlockDivisionState::new(&basis).expect("Should create.");
 
-        let id1 = "Test Block Division 3";
-        match PersistentDivision::delete_division(&mut conn, id1.to_string()) {
-            Ok(_) => {}
-            Err(_) => {
-                eprintln!("Couldn't delete pre-existing pd, but this may not be an error.");
+        let verification = bds.verify_ranks().expect("Should verify.");
+        assert!(verification.matches);
+        assert!(verification.mismatches.is_empty());
+
+        let reseeded = BlockDivisionState::from_lottery(
+            &basis,
+            bds.get_lottery().clone().expect("Should be seeded."),
+        )
+        .expect("Should create.");
+        assert!(reseeded.bucket_states == bds.bucket_states);
+
+        //Swap two participants' ranks in one bucket and round, which a fair draw from this seed can't have produced.
+        let tampered_bucket = BUCKET_INDICES[2];
+        let ranks = bds
+            .get_bucket_states_mut()
+            .get_mut(tampered_bucket)
+            .expect("Should exist.")
+            .get_state_mut(&ROUND_2.0)
+            .ranks
+            .as_mut()
+            .expect("Should be some.");
+        let rank_a = ranks[&PARTICIPANT_A.0];
+        let rank_b = ranks[&PARTICIPANT_B.0];
+        ranks.insert(PARTICIPANT_A.0, rank_b);
+        ranks.insert(PARTICIPANT_B.0, rank_a);
+
+        let verification = bds.verify_ranks().expect("Should verify.");
+        assert!(!verification.matches);
+        assert_eq!(
+            verification.mismatches,
+            Vec::from([RankMismatch {
+                bucket_index: tampered_bucket,
+                round_index: ROUND_2.0
+            }])
+        );
+
+        bds.censor_lottery();
+        assert!(bds.verify_ranks().is_err());
+    }
+
+    #[test]
+    fn amendments_keep_ranks_and_selections() {
+        let mut bds = BlockDivisionState::new(&create_basis_with_slots(1)).expect("Should create.");
+        let round = ROUND_1.0;
+        let ranks = |bds: &BlockDivisionState, bucket: usize| {
+            bds.bucket_states[bucket]
+                .get_state(&round)
+                .ranks
+                .clone()
+                .expect("Should be some.")
+        };
+        let order = |bds: &BlockDivisionState, bucket: usize| {
+            let ranks = ranks(bds, bucket);
+            let mut participants = Vec::from([PARTICIPANT_A.0, PARTICIPANT_B.0, PARTICIPANT_C.0]);
+            participants.sort_by_key(|participant| ranks[participant]);
+            participants
+        };
+        let order_before = order(&bds, 0);
+        bds.open_round(Some(round)).expect("Should open.");
+        bds.apply_selections(PARTICIPANT_A.0, Vec::from([pick(1, &[])]))
+            .expect("Should select.");
+        bds.apply_selections(PARTICIPANT_B.0, Vec::from([pick(2, &[])]))
+            .expect("Should select.");
+
+        //A late hire gets ranks everywhere without reshuffling anyone else, and a new bucket is ranked too
+        let late_hire = ParticipantDef::create(
+            "Participant D".to_string(),
+            "testing_d@autoscheda.com".to_string(),
+            Vec::from([PICKS_PER_ROUND; 4]),
+        );
+        amend(&mut bds, BasisAmendment::AddParticipant(late_hire.clone())).expect("Should add.");
+        let mut new_bucket = bds.basis.get_bucket_definitions()[0].clone();
+        new_bucket.name = "Bucket New".to_string();
+        amend(&mut bds, BasisAmendment::AddBucket(new_bucket)).expect("Should add.");
+        let new_bucket_index = BUCKET_INDICES.len();
+        assert!(order(&bds, 0) == order_before);
+        assert!(ranks(&bds, 0).len() == 4);
+        assert!(ranks(&bds, new_bucket_index).len() == 4);
+        assert!(bds.verify_ranks().expect("Should verify.").matches);
+        assert!(get_pick(&bds, round, PARTICIPANT_A.0).state == Some(SelectionResult::Confirmed));
+        assert!(bds.selections.get(&round).expect("Should exist.")[&3] == Vec::from([None]));
+
+        //Duplicate emails and empty buckets are rejected without changing anything
+        assert!(amend(&mut bds, BasisAmendment::AddParticipant(late_hire)).is_err());
+        assert!(amend(
+            &mut bds,
+            BasisAmendment::SetSlots {
+                bucket_index: 0,
+                slots: 0
             }
-        }
+        )
+        .is_err());
+        assert!(bds.amendments.len() == 2);
+
+        //Removing a bucket rejects picks for it, and removing a participant frees what they held
+        amend(&mut bds, BasisAmendment::RemoveBucket(2)).expect("Should remove.");
+        assert!(
+            get_pick(&bds, round, PARTICIPANT_B.0).state
+                == Some(SelectionResult::RejectedBucketRemoved)
+        );
+        assert!(bds
+            .apply_selections(PARTICIPANT_B.0, Vec::from([pick(2, &[])]))
+            .is_err());
+        bds.apply_selections(PARTICIPANT_B.0, Vec::from([pick(1, &[])]))
+            .expect("Should select.");
+        amend(&mut bds, BasisAmendment::RemoveParticipant(PARTICIPANT_A.0))
+            .expect("Should remove.");
+        assert!(*get_designations(&bds, 1, round) == Designations::from([PARTICIPANT_B.0]));
+        assert!(bds
+            .apply_selections(PARTICIPANT_A.0, Vec::from([pick(3, &[])]))
+            .is_err());
+
+        //Picks can change for rounds that aren't finalized
+        amend(
+            &mut bds,
+            BasisAmendment::SetPicks {
+                participant: PARTICIPANT_C.0,
+                round: ROUND_2.0,
+                picks: 3,
+            },
+        )
+        .expect("Should set.");
+        assert!(
+            bds.selections.get(&ROUND_2.0).expect("Should exist.")[&PARTICIPANT_C.0].len() == 3
+        );
+        bds.finalize_round(ROUND_2.0).expect("Should finalize.");
+        assert!(amend(
+            &mut bds,
+            BasisAmendment::SetPicks {
+                participant: PARTICIPANT_C.0,
+                round: ROUND_2.0,
+                picks: 1,
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn selection_and_calculation() {
+        let mut store = InMemoryDivisionStore::new();
+        let basis = create_basis();
 
-        PersistentDivision::new(&mut conn, id1.to_string(), &basis).expect("Should work."); //Test overwriting
+        let id1 = "Test Block Division 3";
+        store.new_division(id1, &basis).expect("Should work.");
 
         let participant_index = PARTICIPANT_A.0;
 
@@ -607,25 +4156,28 @@ mod tests {
             selections_a.push(Some(Selection {
                 bucket_index: current_bucket_index,
                 ancillaries: ancillaries_a.clone(),
+                fallbacks: Vec::new(),
                 state: None,
+                granted_option: None,
+                explanations: BTreeMap::new(),
             }));
         }
 
-        BlockDivisionState::set_open_round(&mut conn, id1.to_string(), Some(current_round_index))
+        BlockDivisionState::set_open_round(&mut store, id1.to_string(), Some(current_round_index))
             .expect("Couldn't set open round.");
 
         BlockDivisionState::set_selections_for_current_round(
-            &mut conn,
+            &mut store,
             id1.to_string(),
             participant_index,
             selections_a,
         )
         .expect("Should be able to input selection.");
 
-        let bds = PersistentDivision::get_from_id(&mut conn, id1)
-            .expect("Should exist.")
-            .as_state()
-            .expect("Should be a state.");
+        let bds = store
+            .get_state(id1)
+            .expect("Should be a state.")
+            .expect("Should exist.");
         assert!(current_round_index == bds.current_open_round.expect("Should not be none."));
 
         //bds.determine_designations_from_current_selections(); //This is called internally by the set_selections_for_current_fround function.
@@ -653,4 +4205,231 @@ mod tests {
         }
         assert!(correctly_assigned);
     }
+
+    #[test]
+    fn schedule_opens_and_closes_rounds() {
+        let mut store = InMemoryDivisionStore::new();
+        let id = "Test Block Division Schedule";
+        let start = Utc.with_ymd_and_hms(2025, 1, 6, 8, 0, 0).unwrap();
+        let basis = create_basis().with_round_schedules(Vec::from([
+            RoundSchedule::create(Some(start), Some(start + Duration::days(1))),
+            RoundSchedule::create(Some(start + Duration::days(2)), None),
+            RoundSchedule::default(),
+            RoundSchedule::default(),
+        ]));
+        assert!(basis.validate().is_empty());
+        store
+            .insert_state(
+                id,
+                &BlockDivisionState::new(&basis).expect("Should create."),
+            )
+            .expect("Should insert.");
+        let apply = |store: &mut InMemoryDivisionStore, now| {
+            BlockDivisionState::apply_schedule(store, id.to_string(), now)
+                .expect("Should apply schedule.")
+        };
+        let state = |store: &mut InMemoryDivisionStore| {
+            store
+                .get_state(id)
+                .expect("Should get state.")
+                .expect("State should exist.")
+        };
+
+        assert!(apply(&mut store, start - Duration::minutes(1)).is_empty());
+        assert!(
+            apply(&mut store, start)
+                == Vec::from([ScheduledChange {
+                    round: ROUND_1.0,
+                    event: ScheduleEvent::Open
+                }])
+        );
+        assert!(*state(&mut store).get_current_open_round() == Some(ROUND_1.0));
+
+        //Nothing runs twice, even if the admin closes selections in between
+        BlockDivisionState::set_open_round(&mut store, id.to_string(), None)
+            .expect("Should close selections.");
+        assert!(apply(&mut store, start + Duration::hours(1)).is_empty());
+        assert!(state(&mut store).get_current_open_round().is_none());
+
+        //A missed close and open are both caught up, in order
+        assert!(
+            apply(&mut store, start + Duration::days(3))
+                == Vec::from([
+                    ScheduledChange {
+                        round: ROUND_1.0,
+                        event: ScheduleEvent::Close
+                    },
+                    ScheduledChange {
+                        round: ROUND_2.0,
+                        event: ScheduleEvent::Open
+                    }
+                ])
+        );
+        let after = state(&mut store);
+        assert!(after.is_finalized(&ROUND_1.0));
+        assert!(*after.get_current_open_round() == Some(ROUND_2.0));
+
+        //Rescheduling a round runs it again
+        let mut rescheduled = after.clone();
+        amend(
+            &mut rescheduled,
+            BasisAmendment::SetSchedule {
+                round: ROUND_2.0,
+                schedule: RoundSchedule::create(
+                    Some(start + Duration::days(2)),
+                    Some(start + Duration::days(4)),
+                ),
+            },
+        )
+        .expect("Should reschedule.");
+        assert!(
+            rescheduled.next_scheduled_change(start + Duration::days(3))
+                == Some(ScheduledChange {
+                    round: ROUND_2.0,
+                    event: ScheduleEvent::Open
+                })
+        );
+        assert!(amend(
+            &mut rescheduled,
+            BasisAmendment::SetSchedule {
+                round: ROUND_1.0,
+                schedule: RoundSchedule::default(),
+            },
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn reminders_go_to_those_without_picks() {
+        let start = Utc.with_ymd_and_hms(2025, 1, 6, 8, 0, 0).unwrap();
+        let close = start + Duration::days(2);
+        let mut schedules = vec![RoundSchedule::default(); 4];
+        schedules[ROUND_1.0] =
+            RoundSchedule::create(Some(start), Some(close)).with_reminder_hours(Vec::from([24, 2]));
+        let basis = create_basis().with_round_schedules(schedules);
+        assert!(basis.validate().is_empty());
+        let mut bds = BlockDivisionState::new(&basis).expect("Should create.");
+        assert!(bds.due_reminders(close - Duration::hours(1)).is_empty());
+
+        bds.open_round(Some(ROUND_1.0)).expect("Should open.");
+        bds.selections
+            .set(ROUND_1.0, PARTICIPANT_A.0, Vec::from([pick(0, &[])]));
+        let reminded = |bds: &BlockDivisionState, now| -> Vec<(ParticipantIndex, u32)> {
+            bds.due_reminders(now)
+                .iter()
+                .map(|reminder| (reminder.participant, reminder.hours_before_close))
+                .collect()
+        };
+        assert!(reminded(&bds, start).is_empty());
+        assert!(
+            reminded(&bds, close - Duration::hours(23))
+                == Vec::from([(PARTICIPANT_B.0, 24), (PARTICIPANT_C.0, 24)])
+        );
+
+        //Only the nearest reminder is due, and it has its own sent marker
+        let due = bds.due_reminders(close - Duration::hours(1));
+        assert!(due.iter().all(|reminder| reminder.hours_before_close == 2));
+        assert!(
+            due[0].sent_key("id")
+                != Reminder {
+                    hours_before_close: 24,
+                    ..due[0]
+                }
+                .sent_key("id")
+        );
+        assert!(reminded(&bds, close).is_empty());
+    }
+
+    #[test]
+    fn history_replays_to_the_stored_state() {
+        let mut store = InMemoryDivisionStore::new();
+        let id = "Test Block Division History";
+        store
+            .new_division(id, &create_basis_with_slots(1))
+            .expect("Should create.");
+        let stored = |store: &mut InMemoryDivisionStore| {
+            store
+                .get_state(id)
+                .expect("Should get state.")
+                .expect("State should exist.")
+        };
+        let created = stored(&mut store);
+
+        BlockDivisionState::set_open_round(&mut store, id.to_string(), Some(ROUND_1.0))
+            .expect("Should open.");
+        for participant in [PARTICIPANT_A.0, PARTICIPANT_B.0] {
+            BlockDivisionState::set_selections_for_current_round(
+                &mut store,
+                id.to_string(),
+                participant,
+                Vec::from([pick(0, &[1])]),
+            )
+            .expect("Should select.");
+        }
+        let submitted_at = Utc::now();
+        let submitted = stored(&mut store);
+
+        //Additions draw new ranks, which replay has to reproduce
+        BlockDivisionState::amend(
+            &mut store,
+            id.to_string(),
+            BasisAmendment::AddParticipant(ParticipantDef::create(
+                "Participant D".to_string(),
+                "testing_d@autoscheda.com".to_string(),
+                Vec::from([PICKS_PER_ROUND; 4]),
+            )),
+            Utc::now(),
+        )
+        .expect("Should amend.");
+        BlockDivisionState::override_designation(
+            &mut store,
+            id.to_string(),
+            ROUND_1.0,
+            2,
+            PARTICIPANT_C.0,
+            BTreeSet::new(),
+            Utc::now(),
+        )
+        .expect("Should override.");
+        BlockDivisionState::close_round(&mut store, id.to_string(), ROUND_1.0)
+            .expect("Should close.");
+        //A rejected change isn't recorded
+        assert!(
+            BlockDivisionState::set_open_round(&mut store, id.to_string(), Some(ROUND_1.0))
+                .is_err()
+        );
+
+        let state_at = |store: &mut InMemoryDivisionStore, at| {
+            BlockDivisionState::state_at(store, id, &at).expect("Should replay.")
+        };
+        assert!(state_at(&mut store, Utc::now()) == stored(&mut store));
+        assert!(state_at(&mut store, submitted_at) == submitted);
+        let first = *store.get_events(id).expect("Should get events.")[0].get_at();
+        assert!(state_at(&mut store, first) == created);
+        assert!(
+            BlockDivisionState::state_at(&mut store, id, &(first - Duration::seconds(1))).is_err()
+        );
+
+        store.delete_division(id).expect("Should delete.");
+        assert!(BlockDivisionState::state_at(&mut store, id, &Utc::now()).is_err());
+        assert!(state_at(&mut store, submitted_at) == submitted);
+
+        //Divisions from before events were recorded start from their first change
+        let legacy = "Test Block Division Without History";
+        store
+            .insert_state(
+                legacy,
+                &BlockDivisionState::new(&create_basis()).expect("Should create."),
+            )
+            .expect("Should insert.");
+        BlockDivisionState::set_open_round(&mut store, legacy.to_string(), Some(ROUND_1.0))
+            .expect("Should open.");
+        assert!(
+            BlockDivisionState::state_at(&mut store, legacy, &Utc::now()).expect("Should replay.")
+                == store
+                    .get_state(legacy)
+                    .expect("Should get state.")
+                    .expect("State should exist.")
+        );
+    }
 }
@@ -0,0 +1,238 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    bucket::{BucketState, Ranks},
+    lottery::LotteryRecord,
+    participant::ParticipantIndex,
+};
+
+//How participants are ordered when they compete for a bucket or an ancillary.
+//Ranks are drawn once from the division's lottery, so a strategy must always draw the same ranks from the same seed, though different strategies draw different ranks from it.
+pub trait AllocationStrategy {
+    //Ranks indexed by bucket, then round
+    fn draw_ranks(
+        &self,
+        lottery: &LotteryRecord,
+        participant_count: usize,
+        bucket_count: usize,
+        round_count: usize,
+    ) -> Result<Vec<Vec<Ranks>>, Box<dyn std::error::Error>>;
+
+    //Rank of a participant added after the draw, indexed by bucket, then round. existing holds everyone else's ranks, indexed the same way.
+    fn draw_added_participant(
+        &self,
+        lottery: &LotteryRecord,
+        participant: ParticipantIndex,
+        existing: &Vec<Vec<Ranks>>,
+        round_count: usize,
+    ) -> Result<Vec<Vec<usize>>, Box<dyn std::error::Error>>;
+
+    //Ranks of a bucket added after the draw, indexed by round. existing holds the ranks of another bucket, if there is one.
+    fn draw_added_bucket(
+        &self,
+        lottery: &LotteryRecord,
+        participant_count: usize,
+        round_count: usize,
+        existing: Option<&Vec<Ranks>>,
+    ) -> Result<Vec<Ranks>, Box<dyn std::error::Error>>;
+
+    //Lower goes first and wins
+    fn priority(
+        &self,
+        round_state: &BucketState,
+        participant: &ParticipantIndex,
+    ) -> (usize, ParticipantIndex) {
+        (*round_state.get_rank(participant), *participant)
+    }
+
+    fn get_winners(
+        &self,
+        round_state: &BucketState,
+        candidates: &BTreeSet<ParticipantIndex>,
+        winner_count: usize,
+    ) -> BTreeSet<ParticipantIndex> {
+        let mut map: BTreeMap<(usize, ParticipantIndex), ParticipantIndex> = BTreeMap::new(); //automatically sorted by key
+        for candidate in candidates {
+            map.insert(self.priority(round_state, candidate), *candidate);
+        }
+
+        while map.len() > winner_count {
+            map.pop_last();
+        }
+
+        map.into_values().collect()
+    }
+}
+
+//Chosen per division in the basis
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Allocation {
+    #[default]
+    PerBucketLottery, //A separate random rank for every bucket in every round
+    SerialDictatorship, //One random order per round, shared by every bucket
+}
+
+impl Allocation {
+    pub fn strategy(&self) -> &'static dyn AllocationStrategy {
+        match self {
+            Allocation::PerBucketLottery => &PerBucketLottery,
+            Allocation::SerialDictatorship => &SerialDictatorship,
+        }
+    }
+}
+
+pub struct PerBucketLottery;
+
+impl AllocationStrategy for PerBucketLottery {
+    fn draw_ranks(
+        &self,
+        lottery: &LotteryRecord,
+        participant_count: usize,
+        bucket_count: usize,
+        round_count: usize,
+    ) -> Result<Vec<Vec<Ranks>>, Box<dyn std::error::Error>> {
+        lottery.draw_ranks(participant_count, bucket_count, round_count)
+    }
+
+    fn draw_added_participant(
+        &self,
+        lottery: &LotteryRecord,
+        participant: ParticipantIndex,
+        existing: &Vec<Vec<Ranks>>,
+        round_count: usize,
+    ) -> Result<Vec<Vec<usize>>, Box<dyn std::error::Error>> {
+        let mut rng = lottery.rng()?;
+
+        let mut retval: Vec<Vec<usize>> = vec![Vec::new(); existing.len()];
+        for round in 0..round_count {
+            for (bucket_ranks, existing_ranks) in retval.iter_mut().zip(existing) {
+                bucket_ranks.push(rng.insertion_rank(participant, &existing_ranks[round]));
+            }
+        }
+
+        Ok(retval)
+    }
+
+    fn draw_added_bucket(
+        &self,
+        lottery: &LotteryRecord,
+        participant_count: usize,
+        round_count: usize,
+        _existing: Option<&Vec<Ranks>>,
+    ) -> Result<Vec<Ranks>, Box<dyn std::error::Error>> {
+        let mut rng = lottery.rng()?;
+        Ok((0..round_count)
+            .map(|_| rng.permutation(participant_count))
+            .collect())
+    }
+}
+
+pub struct SerialDictatorship;
+
+impl AllocationStrategy for SerialDictatorship {
+    fn draw_ranks(
+        &self,
+        lottery: &LotteryRecord,
+        participant_count: usize,
+        bucket_count: usize,
+        round_count: usize,
+    ) -> Result<Vec<Vec<Ranks>>, Box<dyn std::error::Error>> {
+        let mut rng = lottery.rng()?;
+
+        let mut retval: Vec<Vec<Ranks>> = Vec::new();
+        for _ in 0..bucket_count {
+            retval.push(Vec::new());
+        }
+
+        for _round in 0..round_count {
+            let order = rng.permutation(participant_count);
+            for bucket_ranks in retval.iter_mut() {
+                bucket_ranks.push(order.clone());
+            }
+        }
+
+        Ok(retval)
+    }
+
+    fn draw_added_participant(
+        &self,
+        lottery: &LotteryRecord,
+        participant: ParticipantIndex,
+        existing: &Vec<Vec<Ranks>>,
+        round_count: usize,
+    ) -> Result<Vec<Vec<usize>>, Box<dyn std::error::Error>> {
+        let mut rng = lottery.rng()?;
+
+        let mut retval: Vec<Vec<usize>> = vec![Vec::new(); existing.len()];
+        let shared = match existing.first() {
+            Some(shared) => shared,
+            None => return Ok(retval),
+        };
+        for round in 0..round_count {
+            let rank = rng.insertion_rank(participant, &shared[round]);
+            for bucket_ranks in retval.iter_mut() {
+                bucket_ranks.push(rank);
+            }
+        }
+
+        Ok(retval)
+    }
+
+    //Every bucket shares the round's order, so a new bucket copies it
+    fn draw_added_bucket(
+        &self,
+        lottery: &LotteryRecord,
+        participant_count: usize,
+        round_count: usize,
+        existing: Option<&Vec<Ranks>>,
+    ) -> Result<Vec<Ranks>, Box<dyn std::error::Error>> {
+        match existing {
+            Some(existing) => Ok(existing.clone()),
+            None => {
+                let mut rng = lottery.rng()?;
+                Ok((0..round_count)
+                    .map(|_| rng.permutation(participant_count))
+                    .collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serial_dictatorship_shares_one_order_per_round() {
+        let lottery = LotteryRecord::random();
+        let ranks = Allocation::SerialDictatorship
+            .strategy()
+            .draw_ranks(&lottery, 5, 3, 2)
+            .expect("Should draw.");
+
+        for round in 0..2 {
+            assert!(ranks[0][round] == ranks[1][round]);
+            assert!(ranks[0][round] == ranks[2][round]);
+        }
+        assert!(
+            ranks
+                == SerialDictatorship
+                    .draw_ranks(&lottery, 5, 3, 2)
+                    .expect("Should draw.")
+        );
+    }
+
+    #[test]
+    fn allocation_defaults_to_per_bucket_lottery() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            #[serde(default)]
+            allocation: Allocation,
+        }
+
+        let wrapper: Wrapper = serde_json::from_str("{}").expect("Should deserialize.");
+        assert!(wrapper.allocation == Allocation::PerBucketLottery);
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{bucket::BucketIndex, participant::ParticipantIndex, round::RoundIndex};
+
+pub type SwapIndex = usize;
+
+//A designation to a bucket in a round
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Holding {
+    pub(crate) round: RoundIndex,
+    pub(crate) bucket_index: BucketIndex,
+}
+
+impl Holding {
+    pub fn create(round: RoundIndex, bucket_index: BucketIndex) -> Holding {
+        Holding {
+            round: round,
+            bucket_index: bucket_index,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SwapStatus {
+    Proposed,
+    Accepted, //Applied to the designations
+    Declined,
+    Cancelled, //Withdrawn by the proposer
+}
+
+//The proposer gives up their holding for the recipient's. Kept after it's resolved as a record of the trade.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct SwapProposal {
+    pub(crate) proposer: ParticipantIndex,
+    pub(crate) proposer_holding: Holding,
+    pub(crate) recipient: ParticipantIndex,
+    pub(crate) recipient_holding: Holding,
+    pub(crate) status: SwapStatus,
+    pub(crate) proposed_at: DateTime<Utc>,
+    pub(crate) resolved_at: Option<DateTime<Utc>>,
+}
+
+impl SwapProposal {
+    pub fn get_status(&self) -> SwapStatus {
+        self.status
+    }
+
+    pub fn get_proposer(&self) -> ParticipantIndex {
+        self.proposer
+    }
+
+    pub fn get_proposer_holding(&self) -> &Holding {
+        &self.proposer_holding
+    }
+
+    pub fn get_recipient(&self) -> ParticipantIndex {
+        self.recipient
+    }
+
+    pub fn get_recipient_holding(&self) -> &Holding {
+        &self.recipient_holding
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{bucket::BucketIndex, participant::ParticipantIndex, round::RoundIndex};
+
+//An outranked pick, kept in rank order so it can take the place of a holder who leaves
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct WaitlistEntry {
+    pub(crate) participant: ParticipantIndex,
+    pub(crate) pick_index: usize,
+    pub(crate) option_index: usize, //0 is the first choice, n is fallbacks[n-1]
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Releaser {
+    Admin,
+    Participant,
+}
+
+//Who took a participant out of a bucket and when. Recomputing never puts them back.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Release {
+    pub(crate) by: Releaser,
+    pub(crate) at: DateTime<Utc>,
+}
+
+impl Release {
+    pub fn create(by: Releaser, at: DateTime<Utc>) -> Release {
+        Release { by: by, at: at }
+    }
+}
+
+//Who may take a participant out of a bucket once its round is finalized. Anyone may while the round is open.
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum WithdrawalPolicy {
+    Frozen, //Finalized rounds never change
+    #[default]
+    AdminOnly,
+    Anyone,
+}
+
+impl WithdrawalPolicy {
+    pub fn allows(&self, by: Releaser) -> bool {
+        match self {
+            WithdrawalPolicy::Frozen => false,
+            WithdrawalPolicy::AdminOnly => by == Releaser::Admin,
+            WithdrawalPolicy::Anyone => true,
+        }
+    }
+}
+
+//A designation that exists only because someone else left
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct Promotion {
+    pub round: RoundIndex,
+    pub bucket_index: BucketIndex,
+    pub participant: ParticipantIndex,
+    #[serde(default)]
+    pub notified: bool,
+}
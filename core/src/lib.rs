@@ -1,8 +1,9 @@
 use std::net::{IpAddr, Ipv4Addr};
 
 use db::database_url;
+use db::{memory::InMemoryDivisionStore, store::DivisionStore};
 use diesel::{
-    r2d2::{ConnectionManager, Pool},
+    r2d2::{ConnectionManager, ManageConnection, Pool},
     PgConnection,
 };
 use diesel_migrations::MigrationHarness;
@@ -26,7 +27,18 @@ pub const MIGRATIONS: diesel_migrations::EmbeddedMigrations =
 
 pub async fn tokio_serve<'a>(
     enable_auth: bool,
+    in_memory: bool,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if in_memory {
+        println!("Starting in-memory store. Nothing will persist after shutdown.");
+        let db_handler = Pool::builder()
+            .build(InMemoryDivisionStore::new())
+            .expect("Could not build in-memory pool");
+
+        println!("Building server");
+        return serve(PostHandler::new(db_handler, enable_auth)).await;
+    }
+
     println!("Starting database transaction handler");
     //let mut db_handler: AsyncDatabaseTransactionHandler<DatabaseTransaction, PgConnection> =
     //    AsyncDatabaseTransactionHandler::new(establish_connection);
@@ -53,7 +65,16 @@ pub async fn tokio_serve<'a>(
     };
 
     println!("Building server");
-    let service = PostHandler::new(db_handler, enable_auth);
+    serve(PostHandler::new(db_handler, enable_auth)).await
+}
+
+async fn serve<M>(service: PostHandler<M>) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    M: ManageConnection,
+    M::Connection: DivisionStore,
+{
+    let scheduler = service.clone();
+    std::thread::spawn(move || scheduler.run_schedule()); //Outlives server restarts below
 
     loop {
         println!("Starting server.");
@@ -61,7 +82,7 @@ pub async fn tokio_serve<'a>(
         let server = spawn_server(
             IpAddr::V4(Ipv4Addr::UNSPECIFIED),
             PORT,
-            StatefulService::<PostHandler>::create(service.clone()),
+            StatefulService::<PostHandler<M>>::create(service.clone()),
         );
 
         println!("Server up.");
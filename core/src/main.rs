@@ -1,6 +1,7 @@
 use std::error::Error;
 
 const INSECURE_MODE_ARG: &str = "--insecure";
+const IN_MEMORY_MODE_ARG: &str = "--in-memory";
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -8,6 +9,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let args: Vec<String> = std::env::args().collect();
     let insecure_mode = args.contains(&INSECURE_MODE_ARG.to_string());
     let enable_auth = !insecure_mode;
+    let in_memory = args.contains(&IN_MEMORY_MODE_ARG.to_string());
 
     println!("Checking for local environment variables.");
     let _ = dotenvy::dotenv().is_err_and(|_| {
@@ -17,7 +19,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     loop {
         println!("Starting Block Divider");
-        let server_handle = tokio::spawn(block_divider::tokio_serve(enable_auth));
+        let server_handle = tokio::spawn(block_divider::tokio_serve(enable_auth, in_memory));
         match server_handle.await {
             Ok(_) => {
                 println!("Server shut down gracefully.");
@@ -7,6 +7,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    division_events (id) {
+        id -> Int8,
+        division_id -> Text,
+        serialized -> Text,
+    }
+}
+
 diesel::table! {
     key_val_store (key) {
         key -> Text,
@@ -14,6 +22,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    ledger_entries (division_id, email) {
+        division_id -> Text,
+        email -> Text,
+        serialized -> Text,
+    }
+}
+
 diesel::table! {
     users (email) {
         email -> Text,
@@ -23,7 +39,9 @@ diesel::table! {
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
+    division_events,
     divisions,
     key_val_store,
+    ledger_entries,
     users,
 );
@@ -1,20 +1,13 @@
-use hyper_util::client::legacy::connect::Connect;
-use serde::Serialize;
-use std::{borrow::BorrowMut, collections::BTreeMap, future::Future, num::IntErrorKind, pin::Pin, sync::Arc};
-use tokio::sync::{mpsc::{self, UnboundedReceiver, UnboundedSender}};
-
-use diesel::{
-    r2d2::{ConnectionManager, Pool, PooledConnection},
-    IntoSql, PgConnection,
-};
-use http_body_util::{combinators::BoxBody, BodyExt, Full};
+use std::{collections::{BTreeMap, BTreeSet}, sync::Arc};
+use chrono::Utc;
+
+use diesel::r2d2::{ManageConnection, Pool, PooledConnection};
 use hyper::{
-    body::{Bytes, Frame, Incoming},
-    service::Service,
+    body::Incoming,
     Method, Request, Response,
 };
 use hyper_services::{
-    commons::{Handler, HandlerBody, HandlerError, HandlerFuture, HandlerResult},
+    commons::{Handler, HandlerBody, HandlerResult},
     cors::permit_all_cors,
     generic_json_error::{generic_json_error, generic_json_error_from_debug},
     request_processing::{basic_authentication_decode, check_basic_authentication, get_request_body_as_string},
@@ -23,28 +16,44 @@ use hyper_services::{
 };
 
 use crate::{
-    db::{division::PersistentDivision, key_value::KeyValuePair}, division::{bucket, state::BlockDivisionState}, server::{requests::{block_division_user_view::UserView, BlockDivisionPost}, responses::SingleBlockDivisionState}
+    db::store::DivisionStore, division::{analysis::{self, Preferences}, basis::BlockDivisionBasis, calendar, ledger::Ledger, results, round::RoundIndex, state::BlockDivisionState, swap::SwapIndex, waitlist::{Promotion, Release, Releaser}}, server::{requests::{block_division_analyze::AnalyzeRequest, block_division_user_view::UserView, BlockDivisionPost}, responses::SingleBlockDivisionState}
 };
 
 use super::responses::BlockDivisionServerResponse;
 
-#[derive(Clone)]
-pub struct PostHandler {
-    database_transaction_handler: Pool<ConnectionManager<PgConnection>>,
+pub struct PostHandler<M:ManageConnection> {
+    database_transaction_handler: Pool<M>,
     block_division_write_mutices: Arc<std::sync::Mutex<BTreeMap<String,Arc<std::sync::Mutex<()>>>>>,
     enable_auth: bool
 }
 
+impl<M:ManageConnection> Clone for PostHandler<M> { //Derive would require M: Clone, which ConnectionManager<PgConnection> isn't
+    fn clone(&self) -> Self {
+        PostHandler {
+            database_transaction_handler: self.database_transaction_handler.clone(),
+            block_division_write_mutices: self.block_division_write_mutices.clone(),
+            enable_auth: self.enable_auth
+        }
+    }
+}
+
 const BLOCK_DIVISION: &str = "/block_division_post";
+const BLOCK_DIVISION_CALENDAR: &str = "/block_division_calendar";
+const DIVISION_SCOPE: &str = "division";
 const ADMIN: &str = "admin";
+const SCHEDULE_INTERVAL_SECONDS: u64 = 60;
+const PUBLIC_ORIGIN: &str = "PUBLIC_ORIGIN"; //Env var with the page linked from e-mails sent outside a request, like reminders
+const MAX_SEND_ATTEMPTS: u32 = 3; //Scheduled e-mails that fail this many times are given up on
 
 fn validator (str:&str)->bool{println!("Received credentials: {}",str);basic_authentication_decode(str)==Some(("tyler".to_string(),"notanotherweakpassword!".to_string()))}
 
-impl StatefulHandler for PostHandler {
+impl<M> StatefulHandler for PostHandler<M>
+where M:ManageConnection, M::Connection:DivisionStore
+{
     async fn handle_request(mut self: Self, request: Request<Incoming>) -> HandlerResult {
         let (parts, body) = request.into_parts();
         let method = &parts.method;
-        let (path,_query)=match parts.uri.path_and_query()
+        let (path,query)=match parts.uri.path_and_query()
         {
             Some(pq)=>{(pq.path(),pq.query())},
             None=>("",None)
@@ -80,6 +89,9 @@ impl StatefulHandler for PostHandler {
                 println!("Block division post.");
                 Self::handle_post(&mut self, parts,body).await
             }
+            (&Method::GET, BLOCK_DIVISION_CALENDAR) => {
+                Ok(permit_all_cors(self.handle_calendar(query)))
+            }
             (&Method::GET, path) => {
                 let root = std::env::var("FILE_ROOT").expect("FILE_ROOT must be set");
                 send_file(&root, path).await
@@ -92,8 +104,10 @@ impl StatefulHandler for PostHandler {
     }
 }
 
-impl PostHandler {
-    pub fn new(database_transaction_handler: Pool<ConnectionManager<PgConnection>>, enable_auth:bool) -> PostHandler {
+impl<M> PostHandler<M>
+where M:ManageConnection, M::Connection:DivisionStore
+{
+    pub fn new(database_transaction_handler: Pool<M>, enable_auth:bool) -> PostHandler<M> {
         PostHandler {
             database_transaction_handler: database_transaction_handler,
             block_division_write_mutices: Arc::new(std::sync::Mutex::new(BTreeMap::new())),
@@ -123,13 +137,52 @@ impl PostHandler {
 
     fn get_conn(
         &self,
-    ) -> Result<PooledConnection<ConnectionManager<PgConnection>>, Box<dyn std::error::Error>> {
+    ) -> Result<PooledConnection<M>, Box<dyn std::error::Error>> {
         match self.database_transaction_handler.get() {
             Ok(conn) => Ok(conn),
             Err(e) => Err(Box::new(e)),
         }
     }
 
+    //Opens and closes scheduled rounds. Schedules are re-read from the database every pass, so a restart picks up where it left off.
+    //Each pass blocks on the database, division locks and SMTP, so this runs on its own thread rather than a tokio worker.
+    pub fn run_schedule(self)
+    {
+        loop {
+            if let Err(e) = self.apply_schedules() {
+                eprintln!("Couldn't apply round schedules: {:?}", e);
+            }
+            std::thread::sleep(std::time::Duration::from_secs(SCHEDULE_INTERVAL_SECONDS));
+        }
+    }
+
+    fn apply_schedules(&self) -> Result<(), Box<dyn std::error::Error>>
+    {
+        let ids:Vec<String> = self.get_conn()?.get_all_states()?.into_keys().collect();
+        for id in ids {
+            let lock = self.get_block_division_lock(&id)?;
+            let _guard = match lock.lock() {
+                Ok(guard) => guard,
+                Err(_) => {
+                    lock.clear_poison();
+                    continue; //Tried again next pass
+                }
+            };
+            let mut conn = self.get_conn()?;
+            let store:&mut dyn DivisionStore = &mut *conn;
+            let before = finalized_rounds(store, &id);
+            match BlockDivisionState::apply_schedule(store, id.clone(), Utc::now()) {
+                Ok(applied) => for change in applied {
+                    println!("Scheduled {:?} of round {} in {}.", change.event, change.round, id);
+                },
+                Err(e) => eprintln!("Couldn't apply the schedule of {}: {:?}", id, e),
+            }
+            notify_round_results(store, &id, &before, std::env::var(PUBLIC_ORIGIN).ok().as_deref());
+            send_reminders(store, &id);
+        }
+        Ok(())
+    }
+
     fn run_in_lock<T>(&self, id:&str, func:T) -> Response<HandlerBody> 
     where T:FnOnce()->Response<HandlerBody>
     {
@@ -152,6 +205,44 @@ impl PostHandler {
         }
     }
 
+    //Reached from the participant's link, e.g. /block_division_calendar?hash=123 or /block_division_calendar?hash=123&scope=division
+    fn handle_calendar(&self, query:Option<&str>) -> Response<HandlerBody> {
+        let mut hash = None;
+        let mut whole_division = false;
+        for pair in query.unwrap_or("").split('&') {
+            match pair.split_once('=') {
+                Some(("hash",value))=>hash=Some(value),
+                Some(("scope",value))=>whole_division= value==DIVISION_SCOPE,
+                _=>()
+            }
+        }
+
+        let hash = match hash {
+            Some(hash)=>hash,
+            None=>return generic_json_error("Calendar request contained no hash.")
+        };
+
+        let mut conn = match self.get_conn() {
+            Ok(conn) => conn,
+            Err(err) => return generic_json_error_from_debug(err),
+        };
+        let store:&mut dyn DivisionStore = &mut *conn;
+
+        match UserView::get(store, hash) {
+            Ok(user_view) => {
+                let user_id = match whole_division {
+                    true => None,
+                    false => Some(user_view.get_user_id() as usize)
+                };
+                match get_calendar(store, user_view.get_state_id(), user_id) {
+                    Ok(ics) => calendar_response(ics),
+                    Err(e) => generic_json_error_from_debug(e),
+                }
+            }
+            Err(e) => generic_json_error_from_debug(e),
+        }
+    }
+
     async fn handle_post(&mut self, parts: hyper::http::request::Parts, body:Incoming) -> HandlerResult {
 
         let as_string = get_request_body_as_string(body).await?;
@@ -179,7 +270,24 @@ impl PostHandler {
                         BlockDivisionPost::NewBasis(_) => Some(ADMIN),
                         BlockDivisionPost::DeleteState(_) => Some(ADMIN),
                         BlockDivisionPost::SendStartEmail(_) => Some(ADMIN),
-                        BlockDivisionPost::GetUserViewAsAdmin(_)=>Some(ADMIN)
+                        BlockDivisionPost::GetUserViewAsAdmin(_)=>Some(ADMIN),
+                        BlockDivisionPost::VerifyRanks(_)=>Some(ADMIN),
+                        BlockDivisionPost::CloseRound(_)=>Some(ADMIN),
+                        BlockDivisionPost::GetCalendar(_)=>Some(ADMIN),
+                        BlockDivisionPost::GetLedger(_)=>Some(ADMIN),
+                        BlockDivisionPost::RecordLedger(_)=>Some(ADMIN),
+                        BlockDivisionPost::RemoveDesignation(_)=>Some(ADMIN),
+                        BlockDivisionPost::Withdraw(_)=>None,
+                        BlockDivisionPost::SetWithdrawalPolicy(_)=>Some(ADMIN),
+                        BlockDivisionPost::ProposeSwap(_)=>None,
+                        BlockDivisionPost::RespondToSwap(_)=>None,
+                        BlockDivisionPost::SimulateSelections(_)=>None,
+                        BlockDivisionPost::Analyze(_)=>Some(ADMIN),
+                        BlockDivisionPost::AmendBasis(_)=>Some(ADMIN),
+                        BlockDivisionPost::OverrideDesignation(_)=>Some(ADMIN),
+                        BlockDivisionPost::RemoveOverride(_)=>Some(ADMIN),
+                        BlockDivisionPost::GetStateAt(_)=>Some(ADMIN),
+                        BlockDivisionPost::GetHistory(_)=>Some(ADMIN)
                     };
 
                     match auth_realm {
@@ -198,19 +306,42 @@ impl PostHandler {
                     }
 
                     //Handle if passed auth
+                    let store:&mut dyn DivisionStore = &mut *conn;
                     match request_body {
                         BlockDivisionPost::GetStates(_) => {
-                            let res = PersistentDivision::get_all(&mut conn)
+                            let res = store.get_all_states()
                                 .expect("Couldn't get all from persistent division table.");
                             get_response(Some(res))
                         }
                         BlockDivisionPost::SetOpenRound(set_round_request) => {
                             let id = set_round_request.get_id().to_string();
+                            let origin = parts.headers.get(hyper::header::ORIGIN).and_then(|origin|origin.to_str().ok()).map(|origin|origin.to_string());
                             let func = ||{
-                                let res = BlockDivisionState::set_open_round(&mut conn, set_round_request.get_id().to_string(), *set_round_request.get_round());
+                                let before = finalized_rounds(store, &id);
+                                let res = BlockDivisionState::set_open_round(store, set_round_request.get_id().to_string(), *set_round_request.get_round());
                                 match res
                                 {
-                                    Ok(_) => get_response(Some(true)),
+                                    Ok(_) => {
+                                        notify_round_results(store, &id, &before, origin.as_deref());
+                                        get_response(Some(true))
+                                    },
+                                    Err(e) => generic_json_error_from_debug(e),
+                                }
+                            };
+                            self.run_in_lock(&id, func)
+                        }
+                        BlockDivisionPost::CloseRound(close_round_request) => {
+                            let id = close_round_request.get_id().to_string();
+                            let origin = parts.headers.get(hyper::header::ORIGIN).and_then(|origin|origin.to_str().ok()).map(|origin|origin.to_string());
+                            let func = ||{
+                                let before = finalized_rounds(store, &id);
+                                let res = BlockDivisionState::close_round(store, close_round_request.get_id().to_string(), close_round_request.get_round());
+                                match res
+                                {
+                                    Ok(_) => {
+                                        notify_round_results(store, &id, &before, origin.as_deref());
+                                        get_response(Some(true))
+                                    },
                                     Err(e) => generic_json_error_from_debug(e),
                                 }
                             };
@@ -218,34 +349,31 @@ impl PostHandler {
                         }
                         BlockDivisionPost::NewBasis(new_basis_request) => {
                             println!("New persistent division.");
-                            let res = match PersistentDivision::new(
-                                &mut conn,
-                                new_basis_request.get_id().to_string(),
-                                new_basis_request.get_basis(),
-                            ) {
-                                Ok(_) => true,
-                                Err(_) => false,
-                            };
-    
-                            get_response(Some(res))
+                            let errors = new_basis_request.get_basis().validate();
+                            if !errors.is_empty() {
+                                get_response(Some(errors))
+                            } else {
+                                let res = store.new_division(
+                                    new_basis_request.get_id(),
+                                    new_basis_request.get_basis(),
+                                ).is_ok();
+        
+                                get_response(Some(res))
+                            }
                         }
                         BlockDivisionPost::DeleteState(delete_state_request) => {
                             println!("Delete division.");
-                            let res = match PersistentDivision::delete_division(
-                                &mut conn,
-                                delete_state_request.get_id().to_string(),
-                            ) {
-                                Ok(_) => true,
-                                Err(_) => false,
-                            };
+                            let res = store.delete_division(
+                                delete_state_request.get_id(),
+                            ).is_ok();
     
                             get_response(Some(res))
                         }
                         BlockDivisionPost::GetUserView(get_user_view_request) => {
-                            match UserView::get(&mut conn, get_user_view_request.get_hash()) {
+                            match UserView::get(store, get_user_view_request.get_hash()) {
                                 Ok(user_view) => {
                                     println!("Got request for {:?}", user_view);
-                                    get_user_view(&mut conn,&user_view)
+                                    get_user_view(store,&user_view)
                                 }
                                 Err(e) => generic_json_error_from_debug(e),
                             }
@@ -254,8 +382,7 @@ impl PostHandler {
                         BlockDivisionPost::SendStartEmail(send_start_email) => {
                             match mail::get_service_from_env() {
                                 Ok(mail_service) => {
-                                    match PersistentDivision::get_state_from_id(
-                                        &mut conn,
+                                    match store.get_state(
                                         send_start_email.get_state_id(),
                                     ) {
                                         Ok(state) => match state {
@@ -274,7 +401,7 @@ impl PostHandler {
                                                                     user.get_email(),
                                                                 ) {
     
-                                                                    match send_start_email.set(&mut conn)
+                                                                    match send_start_email.set(store)
                                                                     {
                                                                         Ok(_)=>{
                                                                             let subject = format!(
@@ -336,7 +463,7 @@ impl PostHandler {
                                                             None => generic_json_error("No such user."),
                                                         }
                                                     }
-                                                    Err(e) => generic_json_error("Invalid index."),
+                                                    Err(_) => generic_json_error("Invalid index."),
                                                 }
                                             }
                                             None => generic_json_error("No such state."),
@@ -350,20 +477,208 @@ impl PostHandler {
                         BlockDivisionPost::SubmitSelections(submit_selections) => {
                             let id = submit_selections.state_id.to_string();
                             let func = ||{
-                                match BlockDivisionState::set_selections_for_current_round(&mut conn, 
+                                match BlockDivisionState::set_selections_for_current_round(store, 
                                     submit_selections.state_id.to_string(),  
                                     submit_selections.user_id, 
                                     submit_selections.selections){
                                         Ok(_) => {
-                                            get_user_view(&mut conn,&UserView::create(submit_selections.user_id as i32,submit_selections.state_id))
+                                            get_user_view(store,&UserView::create(submit_selections.user_id as i32,submit_selections.state_id))
                                         },
                                         Err(e) => generic_json_error_from_debug(e),
                                     }
                             };
                             self.run_in_lock(&id, func)
                         },
+                        BlockDivisionPost::SimulateSelections(simulate_selections) => {
+                            match store.get_state(&simulate_selections.state_id) {
+                                Ok(Some(state)) => match state.simulate_selections(simulate_selections.user_id, simulate_selections.selections) {
+                                    Ok(projected) => get_response(Some(projected)),
+                                    Err(e) => generic_json_error_from_debug(e),
+                                },
+                                Ok(None) => generic_json_error("No such state."),
+                                Err(e) => generic_json_error_from_debug(e),
+                            }
+                        },
                         BlockDivisionPost::GetUserViewAsAdmin(user_view)=>{
-                            get_user_view(&mut conn,&user_view)
+                            get_user_view(store,&user_view)
+                        }
+                        BlockDivisionPost::VerifyRanks(verify_ranks_request)=>{
+                            match store.get_state(verify_ranks_request.get_id()) {
+                                Ok(Some(state)) => match state.verify_ranks() {
+                                    Ok(verification) => get_response(Some(verification)),
+                                    Err(e) => generic_json_error_from_debug(e),
+                                },
+                                Ok(None) => generic_json_error("No such state."),
+                                Err(e) => generic_json_error_from_debug(e),
+                            }
+                        }
+                        BlockDivisionPost::GetLedger(ledger_request)=>{
+                            match store.get_ledger(ledger_request.get_email()) {
+                                Ok(ledger) => get_response(Some(ledger)),
+                                Err(e) => generic_json_error_from_debug(e),
+                            }
+                        }
+                        BlockDivisionPost::RecordLedger(record_request)=>{
+                            let id = record_request.get_id().to_string();
+                            let func = ||{
+                                match BlockDivisionState::record_in_ledger(store, record_request.get_id().to_string()) {
+                                    Ok(_) => get_response(Some(true)),
+                                    Err(e) => generic_json_error_from_debug(e),
+                                }
+                            };
+                            self.run_in_lock(&id, func)
+                        }
+                        BlockDivisionPost::RemoveDesignation(remove_request)=>{
+                            let id = remove_request.get_id().to_string();
+                            let origin = parts.headers.get(hyper::header::ORIGIN).and_then(|origin|origin.to_str().ok()).map(|origin|origin.to_string());
+                            let func = ||{
+                                match BlockDivisionState::withdraw(store, remove_request.get_id().to_string(), remove_request.get_round(), remove_request.get_bucket_index(), remove_request.get_user_id(), Release::create(Releaser::Admin, Utc::now())) {
+                                    Ok(mut promotions) => {
+                                        notify_promotions(store, remove_request.get_id(), &mut promotions, origin.as_deref());
+                                        get_response(Some(promotions))
+                                    }
+                                    Err(e) => generic_json_error_from_debug(e),
+                                }
+                            };
+                            self.run_in_lock(&id, func)
+                        }
+                        BlockDivisionPost::Withdraw(withdraw_request)=>{
+                            //Only whoever the link belongs to can give up their designation
+                            match UserView::get(store, withdraw_request.get_hash()) {
+                                Ok(user_view) => {
+                                    let id = user_view.get_state_id().to_string();
+                                    let origin = parts.headers.get(hyper::header::ORIGIN).and_then(|origin|origin.to_str().ok()).map(|origin|origin.to_string());
+                                    let func = ||{
+                                        match BlockDivisionState::withdraw(store, id.clone(), withdraw_request.get_round(), withdraw_request.get_bucket_index(), user_view.get_user_id() as usize, Release::create(Releaser::Participant, Utc::now())) {
+                                            Ok(mut promotions) => {
+                                                notify_promotions(store, &id, &mut promotions, origin.as_deref());
+                                                get_user_view(store,&user_view)
+                                            }
+                                            Err(e) => generic_json_error_from_debug(e),
+                                        }
+                                    };
+                                    self.run_in_lock(&id, func)
+                                }
+                                Err(e) => generic_json_error_from_debug(e),
+                            }
+                        }
+                        BlockDivisionPost::SetWithdrawalPolicy(policy_request)=>{
+                            let id = policy_request.get_id().to_string();
+                            let func = ||{
+                                match BlockDivisionState::set_withdrawal_policy(store, policy_request.get_id().to_string(), policy_request.get_policy()) {
+                                    Ok(_) => get_response(Some(true)),
+                                    Err(e) => generic_json_error_from_debug(e),
+                                }
+                            };
+                            self.run_in_lock(&id, func)
+                        }
+                        BlockDivisionPost::AmendBasis(amend_request)=>{
+                            let id = amend_request.get_id().to_string();
+                            let origin = parts.headers.get(hyper::header::ORIGIN).and_then(|origin|origin.to_str().ok()).map(|origin|origin.to_string());
+                            let func = ||{
+                                match BlockDivisionState::amend(store, amend_request.get_id().to_string(), amend_request.get_amendment().clone(), Utc::now()) {
+                                    Ok(mut promotions) => {
+                                        notify_promotions(store, amend_request.get_id(), &mut promotions, origin.as_deref());
+                                        get_response(Some(promotions))
+                                    }
+                                    Err(e) => generic_json_error_from_debug(e),
+                                }
+                            };
+                            self.run_in_lock(&id, func)
+                        }
+                        BlockDivisionPost::OverrideDesignation(override_request)=>{
+                            let id = override_request.get_id().to_string();
+                            let func = ||{
+                                match BlockDivisionState::override_designation(store, override_request.get_id().to_string(), override_request.get_round(), override_request.get_bucket_index(), override_request.get_user_id(), override_request.get_ancillaries().clone(), Utc::now()) {
+                                    Ok(_) => get_response(Some(true)),
+                                    Err(e) => generic_json_error_from_debug(e),
+                                }
+                            };
+                            self.run_in_lock(&id, func)
+                        }
+                        BlockDivisionPost::RemoveOverride(remove_request)=>{
+                            let id = remove_request.get_id().to_string();
+                            let origin = parts.headers.get(hyper::header::ORIGIN).and_then(|origin|origin.to_str().ok()).map(|origin|origin.to_string());
+                            let func = ||{
+                                match BlockDivisionState::remove_override(store, remove_request.get_id().to_string(), remove_request.get_round(), remove_request.get_bucket_index(), remove_request.get_user_id(), Utc::now()) {
+                                    Ok(mut promotions) => {
+                                        notify_promotions(store, remove_request.get_id(), &mut promotions, origin.as_deref());
+                                        get_response(Some(promotions))
+                                    }
+                                    Err(e) => generic_json_error_from_debug(e),
+                                }
+                            };
+                            self.run_in_lock(&id, func)
+                        }
+                        BlockDivisionPost::GetStateAt(state_at_request)=>{
+                            match BlockDivisionState::state_at(store, state_at_request.get_id(), state_at_request.get_at()) {
+                                Ok(state) => get_response(Some(state)),
+                                Err(e) => generic_json_error_from_debug(e),
+                            }
+                        }
+                        BlockDivisionPost::GetHistory(history_request)=>{
+                            match store.get_events(history_request.get_id()) {
+                                Ok(events) => get_response(Some(events)),
+                                Err(e) => generic_json_error_from_debug(e),
+                            }
+                        }
+                        BlockDivisionPost::ProposeSwap(swap_request)=>{
+                            //The proposer is whoever the link belongs to, never an index from the body
+                            match UserView::get(store, swap_request.get_hash()) {
+                                Ok(user_view) => {
+                                    let id = user_view.get_state_id().to_string();
+                                    let origin = parts.headers.get(hyper::header::ORIGIN).and_then(|origin|origin.to_str().ok()).map(|origin|origin.to_string());
+                                    let func = ||{
+                                        match BlockDivisionState::propose_swap(store, id.clone(), user_view.get_user_id() as usize, swap_request.get_give(), swap_request.get_recipient(), swap_request.get_take(), Utc::now()) {
+                                            Ok(swap_index) => {
+                                                notify_swap_proposal(store, &id, swap_index, origin.as_deref());
+                                                get_user_view(store,&user_view)
+                                            }
+                                            Err(e) => generic_json_error_from_debug(e),
+                                        }
+                                    };
+                                    self.run_in_lock(&id, func)
+                                }
+                                Err(e) => generic_json_error_from_debug(e),
+                            }
+                        }
+                        BlockDivisionPost::RespondToSwap(respond_request)=>{
+                            match UserView::get(store, respond_request.get_hash()) {
+                                Ok(user_view) => {
+                                    let id = user_view.get_state_id().to_string();
+                                    let func = ||{
+                                        match BlockDivisionState::respond_to_swap(store, id.clone(), user_view.get_user_id() as usize, respond_request.get_swap_index(), respond_request.get_accept(), Utc::now()) {
+                                            Ok(_) => get_user_view(store,&user_view),
+                                            Err(e) => generic_json_error_from_debug(e),
+                                        }
+                                    };
+                                    self.run_in_lock(&id, func)
+                                }
+                                Err(e) => generic_json_error_from_debug(e),
+                            }
+                        }
+                        BlockDivisionPost::Analyze(analyze_request)=>{
+                            //Up to MAX_RUNS recomputes, so they run on the blocking pool rather than holding up a tokio worker
+                            let inputs = analysis_inputs(store, &analyze_request).map_err(|e|format!("{:?}", e)); //Boxed errors can't be held across the await
+                            match inputs {
+                                Ok((basis, ledger, preferences, runs, seed)) => {
+                                    let analyzed = tokio::task::spawn_blocking(move ||{
+                                        analysis::analyze(&basis, &ledger, &preferences, runs, seed).map_err(|e|format!("{:?}", e))
+                                    }).await;
+                                    match analyzed {
+                                        Ok(Ok(report)) => get_response(Some(report)),
+                                        Ok(Err(e)) => generic_json_error(&e),
+                                        Err(e) => generic_json_error_from_debug(e),
+                                    }
+                                }
+                                Err(e) => generic_json_error(&e),
+                            }
+                        }
+                        BlockDivisionPost::GetCalendar(calendar_request)=>{
+                            match get_calendar(store, calendar_request.get_id(), calendar_request.get_user_id()) {
+                                Ok(ics) => get_response(Some(ics)),
+                                Err(e) => generic_json_error_from_debug(e),
+                            }
                         }
                     }
                 }
@@ -393,6 +708,156 @@ fn email_body(url:&str, hash:&str)->String
         hash)
 }
 
+fn notice_email_body(message:&str, url:Option<&str>, hash:&str)->String
+{
+    let link = match url {
+        Some(url) => format!("<a href=\"{}?hash={}\">Click here</a> to see your selections.", url, hash),
+        None => String::new()
+    };
+    format!(
+        "
+        <!DOCTYPE html>
+        <html>
+        <body>
+        {}
+        {}
+        </body>
+        </html>
+        ",
+        message,
+        link)
+}
+
+//E-mails a participant a notice with their link. False if it couldn't be sent; the change it describes is already saved either way.
+fn notify_participant(store:&mut dyn DivisionStore, state_id:&str, state:&BlockDivisionState, participant:usize, message:&str, origin:Option<&str>)->bool{
+    let user = match state.get_basis().get_participant_definitions().get(participant) {
+        Some(user) if mail::is_valid_email(user.get_email()) => user,
+        _ => return false
+    };
+    let mail_service = match mail::get_service_from_env() {
+        Ok(mail_service) => mail_service,
+        Err(e) => {
+            eprintln!("Couldn't notify {}: {:?}", user.get_email(), e);
+            return false;
+        }
+    };
+    let user_view = UserView::create(participant as i32, state_id.to_string());
+    if let Err(e) = user_view.set(store) {
+        eprintln!("Couldn't store link for {}: {:?}", user.get_email(), e);
+        return false;
+    }
+
+    let body = notice_email_body(message, origin, &user_view.get_hash());
+    match mail::send_mail(&mail_service, user.get_email(), format!("{} - {}", state_id, user.get_name()), body) {
+        Ok(r) => r.is_positive(),
+        Err(e) => {
+            eprintln!("Couldn't send e-mail to {}: {:?}", user.get_email(), e);
+            false
+        }
+    }
+}
+
+fn notify_promotions(store:&mut dyn DivisionStore, state_id:&str, promotions:&mut [Promotion], origin:Option<&str>){
+    let state = match store.get_state(state_id) {
+        Ok(Some(state)) => state,
+        _ => return
+    };
+    for promotion in promotions.iter_mut() {
+        let message = format!("A place opened up and you have been moved off the waitlist into {} for {}.",
+            state.get_basis().get_bucket_definitions()[promotion.bucket_index].name,
+            state.get_basis().get_selection_rounds()[promotion.round]);
+        promotion.notified = notify_participant(store, state_id, &state, promotion.participant, &message, origin);
+    }
+}
+
+fn notify_swap_proposal(store:&mut dyn DivisionStore, state_id:&str, swap_index:SwapIndex, origin:Option<&str>){
+    let state = match store.get_state(state_id) {
+        Ok(Some(state)) => state,
+        _ => return
+    };
+    let proposal = &state.get_swaps()[swap_index];
+    let basis = state.get_basis();
+    let message = format!("{} would like to trade their {} for your {}.",
+        basis.get_participant_definitions()[proposal.get_proposer()].get_name(),
+        basis.get_bucket_definitions()[proposal.get_proposer_holding().bucket_index].name,
+        basis.get_bucket_definitions()[proposal.get_recipient_holding().bucket_index].name);
+    notify_participant(store, state_id, &state, proposal.get_recipient(), &message, origin);
+}
+
+fn finalized_rounds(store:&mut dyn DivisionStore, state_id:&str)->BTreeSet<RoundIndex>{
+    match store.get_state(state_id) {
+        Ok(Some(state)) => state.get_finalized_rounds().clone(),
+        _ => BTreeSet::new()
+    }
+}
+
+//E-mails everyone with picks the results of each round finalized since before. Finalized rounds never reopen, so each is sent once.
+//Results of rounds finalized since before, plus another try at any that failed in rounds finalized earlier
+fn notify_round_results(store:&mut dyn DivisionStore, state_id:&str, before:&BTreeSet<RoundIndex>, origin:Option<&str>){
+    let state = match store.get_state(state_id) {
+        Ok(Some(state)) => state,
+        _ => return
+    };
+    for round in state.get_finalized_rounds() {
+        for participant in 0..state.get_basis().get_participant_definitions().len() {
+            let failed_key = results::failed_key(state_id, *round, participant);
+            if before.contains(round) && store.get_value(&failed_key).is_none() {
+                continue;
+            }
+            if let Some(lines) = results::round_summary(&state, *round, participant) {
+                let message = format!("Results of {}:<br>\n{}",
+                    state.get_basis().get_selection_rounds()[*round],
+                    lines.join("<br>\n"));
+                send_once(store, &results::sent_key(state_id, *round, participant), &failed_key, |store|{
+                    notify_participant(store, state_id, &state, participant, &message, origin)
+                });
+            }
+        }
+    }
+}
+
+//Marked sent only once the e-mail goes out, so a failed one is retried next pass and a restart doesn't repeat any
+fn send_reminders(store:&mut dyn DivisionStore, state_id:&str){
+    let state = match store.get_state(state_id) {
+        Ok(Some(state)) => state,
+        _ => return
+    };
+    let origin = std::env::var(PUBLIC_ORIGIN).ok();
+    for reminder in state.due_reminders(Utc::now()) {
+        let message = format!("{} closes in less than {} hours and you haven't entered your selections yet.",
+            state.get_basis().get_selection_rounds()[reminder.round],
+            reminder.hours_before_close);
+        send_once(store, &reminder.sent_key(state_id), &reminder.failed_key(state_id), |store|{
+            notify_participant(store, state_id, &state, reminder.participant, &message, origin.as_deref())
+        });
+    }
+}
+
+//Sends unless already sent or given up on. Success sets the sent marker, failure counts an attempt under the failed key.
+fn send_once<F>(store:&mut dyn DivisionStore, sent_key:&str, failed_key:&str, send:F)
+where F:FnOnce(&mut dyn DivisionStore)->bool
+{
+    if store.get_value(sent_key).is_some() {
+        return;
+    }
+    let attempts:u32 = store.get_value(failed_key).and_then(|attempts|attempts.parse().ok()).unwrap_or(0);
+    if attempts >= MAX_SEND_ATTEMPTS {
+        return;
+    }
+    let (key, value, allow_overwrite) = match send(store) {
+        true => (sent_key, Utc::now().to_rfc3339(), false),
+        false => {
+            if attempts + 1 == MAX_SEND_ATTEMPTS {
+                eprintln!("Giving up on {} after {} attempts.", sent_key, MAX_SEND_ATTEMPTS);
+            }
+            (failed_key, (attempts + 1).to_string(), true)
+        }
+    };
+    if let Err(e) = store.set_value(key, Some(value), allow_overwrite) {
+        eprintln!("Couldn't record {}: {:?}", key, e);
+    }
+}
+
 fn get_response<T>(
     message: Option<T>,
 ) -> Response<HandlerBody>
@@ -407,9 +872,53 @@ where
     }
 }
 
-fn get_user_view(conn:&mut PgConnection,user_view:&UserView)->Response<HandlerBody>{
-    match PersistentDivision::get_state_from_id(
-        conn,
+//What the analysis runs on: the basis, the ledger for its weights, the preferences, the number of runs and the seed
+type AnalysisInputs = (BlockDivisionBasis, Ledger, Preferences, usize, u64);
+
+fn analysis_inputs(store:&mut dyn DivisionStore, analyze_request:&AnalyzeRequest)->Result<AnalysisInputs,Box<dyn std::error::Error>>{
+    let (basis, submitted) = match (analyze_request.get_id(), analyze_request.get_basis()) {
+        (Some(id), None) => match store.get_state(id)? {
+            Some(state) => (state.get_basis().clone(), Some(analysis::submitted_preferences(&state))),
+            None => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, "No such state.")))
+        },
+        (None, Some(basis)) => {
+            basis.check()?;
+            (basis.clone(), None)
+        }
+        _ => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::InvalidInput, "Give either a division id or a basis.")))
+    };
+    let preferences = match (analyze_request.get_preferences(), submitted) {
+        (Some(preferences), _) => preferences.clone(),
+        (None, Some(submitted)) => submitted,
+        (None, None) => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::InvalidInput, "A basis needs assumed preferences.")))
+    };
+    let ledger = match basis.is_ledger_weighted() {
+        true => store.get_ledger(None)?,
+        false => Ledger::new()
+    };
+    let seed = analyze_request.get_seed().unwrap_or_else(rand::random);
+    Ok((basis, ledger, preferences, analyze_request.get_runs(), seed))
+}
+
+//None for the whole division
+fn get_calendar(store:&mut dyn DivisionStore, state_id:&str, user_id:Option<usize>)->Result<String,Box<dyn std::error::Error>>{
+    match store.get_state(state_id)? {
+        Some(state) => match user_id {
+            Some(user_id) => calendar::participant_calendar(state_id, &state, &user_id, &Utc::now()),
+            None => Ok(calendar::division_calendar(state_id, &state, &Utc::now()))
+        },
+        None => Err(Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, "No such state.")))
+    }
+}
+
+fn calendar_response(ics:String)->Response<HandlerBody>{
+    let mut response = Response::new(full_to_boxed_body(ics));
+    response.headers_mut().insert(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static("text/calendar; charset=utf-8"));
+    response
+}
+
+fn get_user_view(store:&mut dyn DivisionStore,user_view:&UserView)->Response<HandlerBody>{
+    match store.get_state(
         user_view.get_state_id(),
     ) {
         Ok(state) => match state {
@@ -431,14 +940,28 @@ fn get_user_view(conn:&mut PgConnection,user_view:&UserView)->Response<HandlerBo
                     for round in start..fin
                     {
                         bucket_state.get_state_mut(&round).ranks=None;
+                        bucket_state.get_state_mut(&round).waitlist.clear(); //Waitlist order would reveal the censored ranks
                     }
                 }
+                if start<fin
+                {
+                    state.censor_lottery(); //The seed would reveal the censored ranks
+                }
+                for round in start..fin
+                {
+                    state.censor_explanations(&round);
+                }
 
+                let participant = user_view.get_user_id() as usize;
+                let max_buckets = state.get_basis().get_participant_definitions().get(participant).and_then(|participant_def|participant_def.get_max_buckets());
+                let quota_used = state.get_quota_used(&participant);
                 get_response(Some(SingleBlockDivisionState
                     {
                         user_id:user_view.get_user_id(),
                         state_id:user_view.get_state_id().to_string(),
-                        state:state
+                        state,
+                        max_buckets,
+                        quota_used
                     }))
             },None=>{
                 generic_json_error("No such state")
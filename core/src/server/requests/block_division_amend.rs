@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+use crate::division::amendment::BasisAmendment;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct AmendBasisRequest {
+    id: String,
+    amendment: BasisAmendment,
+}
+
+impl AmendBasisRequest {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_amendment(&self) -> &BasisAmendment {
+        &self.amendment
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::division::{analysis::Preferences, basis::BlockDivisionBasis};
+
+//Either a stored division, whose submitted selections are used unless preferences are given, or a basis that hasn't been published yet with assumed preferences
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct AnalyzeRequest {
+    id: Option<String>,
+    basis: Option<BlockDivisionBasis>,
+    preferences: Option<Preferences>,
+    runs: usize,
+    seed: Option<u64>, //Random if not given
+}
+
+impl AnalyzeRequest {
+    pub fn get_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn get_basis(&self) -> &Option<BlockDivisionBasis> {
+        &self.basis
+    }
+
+    pub fn get_preferences(&self) -> &Option<Preferences> {
+        &self.preferences
+    }
+
+    pub fn get_runs(&self) -> usize {
+        self.runs
+    }
+
+    pub fn get_seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct CalendarRequest {
+    id: String,
+    user_id: Option<usize>, //None for the whole division
+}
+
+impl CalendarRequest {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_user_id(&self) -> Option<usize> {
+        self.user_id
+    }
+}
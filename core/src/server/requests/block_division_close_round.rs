@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct CloseRoundRequest {
+    id: String,
+    round: usize,
+}
+
+impl CloseRoundRequest {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_round(&self) -> usize {
+        self.round
+    }
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+//Rebuilds the division from its events as it was at the given time
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct GetStateAtRequest {
+    id: String,
+    at: DateTime<Utc>,
+}
+
+impl GetStateAtRequest {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_at(&self) -> &DateTime<Utc> {
+        &self.at
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct GetHistoryRequest {
+    id: String,
+}
+
+impl GetHistoryRequest {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct GetLedgerRequest {
+    email: Option<String>, //None for everyone
+}
+
+impl GetLedgerRequest {
+    pub fn get_email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct RecordLedgerRequest {
+    id: String,
+}
+
+impl RecordLedgerRequest {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+//Places a participant in a bucket outside the lottery
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct OverrideDesignationRequest {
+    id: String,
+    round: usize,
+    bucket_index: usize,
+    user_id: usize,
+    #[serde(default)]
+    ancillaries: BTreeSet<usize>,
+}
+
+impl OverrideDesignationRequest {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_round(&self) -> usize {
+        self.round
+    }
+
+    pub fn get_bucket_index(&self) -> usize {
+        self.bucket_index
+    }
+
+    pub fn get_user_id(&self) -> usize {
+        self.user_id
+    }
+
+    pub fn get_ancillaries(&self) -> &BTreeSet<usize> {
+        &self.ancillaries
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct RemoveOverrideRequest {
+    id: String,
+    round: usize,
+    bucket_index: usize,
+    user_id: usize,
+}
+
+impl RemoveOverrideRequest {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_round(&self) -> usize {
+        self.round
+    }
+
+    pub fn get_bucket_index(&self) -> usize {
+        self.bucket_index
+    }
+
+    pub fn get_user_id(&self) -> usize {
+        self.user_id
+    }
+}
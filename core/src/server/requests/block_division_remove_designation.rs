@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct RemoveDesignationRequest {
+    id: String,
+    round: usize,
+    bucket_index: usize,
+    user_id: usize,
+}
+
+impl RemoveDesignationRequest {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_round(&self) -> usize {
+        self.round
+    }
+
+    pub fn get_bucket_index(&self) -> usize {
+        self.bucket_index
+    }
+
+    pub fn get_user_id(&self) -> usize {
+        self.user_id
+    }
+}
@@ -16,7 +16,7 @@ pub(crate) struct SubmitSelections {
 }
 
 mod tests {
-    use std::collections::BTreeSet;
+    use std::collections::{BTreeMap, BTreeSet};
 
     use crate::division::selections::Selection;
 
@@ -32,13 +32,19 @@ mod tests {
                 Some(Selection {
                     bucket_index: 5,
                     ancillaries: BTreeSet::from([6, 7]),
+                    fallbacks: Vec::new(),
                     state: None,
+                    granted_option: None,
+                    explanations: BTreeMap::new(),
                 }),
                 None,
                 Some(Selection {
                     bucket_index: 8,
                     ancillaries: BTreeSet::from([9, 10, 11, 12]),
+                    fallbacks: Vec::new(),
                     state: None,
+                    granted_option: None,
+                    explanations: BTreeMap::new(),
                 }),
                 None,
             ]),
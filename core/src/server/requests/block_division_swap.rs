@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+use crate::division::swap::{Holding, SwapIndex};
+
+//The proposer offers their holding in exchange for the recipient's. The proposer and division come from their link's hash.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct ProposeSwapRequest {
+    hash: String,
+    give: Holding,
+    recipient: usize,
+    take: Holding,
+}
+
+impl ProposeSwapRequest {
+    pub fn get_hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn get_give(&self) -> Holding {
+        self.give
+    }
+
+    pub fn get_recipient(&self) -> usize {
+        self.recipient
+    }
+
+    pub fn get_take(&self) -> Holding {
+        self.take
+    }
+}
+
+//The responding participant and division come from their link's hash
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct RespondToSwapRequest {
+    hash: String,
+    swap_index: SwapIndex,
+    accept: bool,
+}
+
+impl RespondToSwapRequest {
+    pub fn get_hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn get_swap_index(&self) -> SwapIndex {
+        self.swap_index
+    }
+
+    pub fn get_accept(&self) -> bool {
+        self.accept
+    }
+}
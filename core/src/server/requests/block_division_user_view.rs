@@ -1,9 +1,8 @@
 use std::hash::{DefaultHasher, Hash, Hasher};
 
-use diesel::PgConnection;
 use serde::{Deserialize, Serialize};
 
-use crate::db::key_value::KeyValuePair;
+use crate::db::store::DivisionStore;
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
 pub(crate) struct GetUserViewRequest {
@@ -47,8 +46,11 @@ impl UserView {
         serde_json::to_string(self).expect("Should serialize.")
     }
 
-    pub fn get(conn: &mut PgConnection, key: &str) -> Result<UserView, Box<dyn std::error::Error>> {
-        match KeyValuePair::get(conn, key) {
+    pub fn get(
+        store: &mut dyn DivisionStore,
+        key: &str,
+    ) -> Result<UserView, Box<dyn std::error::Error>> {
+        match store.get_value(key) {
             Some(str) => match serde_json::from_str::<UserView>(&str) {
                 Ok(res) => Ok(res),
                 Err(e) => Err(Box::new(e)),
@@ -60,8 +62,8 @@ impl UserView {
         }
     }
 
-    pub fn set(&self, conn: &mut PgConnection) -> Result<(), Box<dyn std::error::Error>> {
-        match KeyValuePair::set(conn, &self.get_hash(), Some(self.as_json()), false) {
+    pub fn set(&self, store: &mut dyn DivisionStore) -> Result<(), Box<dyn std::error::Error>> {
+        match store.set_value(&self.get_hash(), Some(self.as_json()), false) {
             Ok(_) => Ok(()),
             Err(e) => Err(e),
         }
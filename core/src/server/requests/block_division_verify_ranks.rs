@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub(crate) struct VerifyRanksRequest {
+    id: String,
+}
+
+impl VerifyRanksRequest {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+}
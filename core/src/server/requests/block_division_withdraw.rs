@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+use crate::division::waitlist::WithdrawalPolicy;
+
+//A participant giving up their own designation. The participant and division come from their link's hash.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct WithdrawRequest {
+    hash: String,
+    round: usize,
+    bucket_index: usize,
+}
+
+impl WithdrawRequest {
+    pub fn get_hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn get_round(&self) -> usize {
+        self.round
+    }
+
+    pub fn get_bucket_index(&self) -> usize {
+        self.bucket_index
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub struct SetWithdrawalPolicyRequest {
+    id: String,
+    policy: WithdrawalPolicy,
+}
+
+impl SetWithdrawalPolicyRequest {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_policy(&self) -> WithdrawalPolicy {
+        self.policy
+    }
+}
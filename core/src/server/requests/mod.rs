@@ -1,17 +1,39 @@
+use block_division_amend::AmendBasisRequest;
+use block_division_analyze::AnalyzeRequest;
+use block_division_calendar::CalendarRequest;
+use block_division_close_round::CloseRoundRequest;
 use block_division_delete::DeleteStateRequest;
+use block_division_history::{GetHistoryRequest, GetStateAtRequest};
+use block_division_ledger::{GetLedgerRequest, RecordLedgerRequest};
 use block_division_list::GetListRequest;
 use block_division_new_basis::NewBasisRequest;
+use block_division_override::{OverrideDesignationRequest, RemoveOverrideRequest};
+use block_division_remove_designation::RemoveDesignationRequest;
 use block_division_set_open_round::SetOpenRoundRequest;
 use block_division_submit_selection::SubmitSelections;
+use block_division_swap::{ProposeSwapRequest, RespondToSwapRequest};
 use block_division_user_view::{GetUserViewRequest, UserView};
+use block_division_verify_ranks::VerifyRanksRequest;
+use block_division_withdraw::{SetWithdrawalPolicyRequest, WithdrawRequest};
 use serde::{Deserialize, Serialize};
 
+pub(crate) mod block_division_amend;
+pub(crate) mod block_division_analyze;
+pub(crate) mod block_division_calendar;
+pub(crate) mod block_division_close_round;
 pub(crate) mod block_division_delete;
+pub(crate) mod block_division_history;
+pub(crate) mod block_division_ledger;
 pub(crate) mod block_division_list;
 pub(crate) mod block_division_new_basis;
+pub(crate) mod block_division_override;
+pub(crate) mod block_division_remove_designation;
 pub(crate) mod block_division_set_open_round;
 pub(crate) mod block_division_submit_selection;
+pub(crate) mod block_division_swap;
 pub(crate) mod block_division_user_view;
+pub(crate) mod block_division_verify_ranks;
+pub(crate) mod block_division_withdraw;
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
 pub(crate) enum BlockDivisionPost {
@@ -24,4 +46,21 @@ pub(crate) enum BlockDivisionPost {
     SendStartEmail(UserView),
     SubmitSelections(SubmitSelections),
     GetUserViewAsAdmin(UserView),
+    VerifyRanks(VerifyRanksRequest),
+    CloseRound(CloseRoundRequest),
+    GetCalendar(CalendarRequest),
+    GetLedger(GetLedgerRequest),
+    RecordLedger(RecordLedgerRequest),
+    RemoveDesignation(RemoveDesignationRequest),
+    Withdraw(WithdrawRequest),
+    SetWithdrawalPolicy(SetWithdrawalPolicyRequest),
+    ProposeSwap(ProposeSwapRequest),
+    RespondToSwap(RespondToSwapRequest),
+    SimulateSelections(SubmitSelections),
+    Analyze(AnalyzeRequest),
+    AmendBasis(AmendBasisRequest),
+    OverrideDesignation(OverrideDesignationRequest),
+    RemoveOverride(RemoveOverrideRequest),
+    GetStateAt(GetStateAtRequest),
+    GetHistory(GetHistoryRequest),
 }
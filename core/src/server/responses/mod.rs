@@ -2,7 +2,11 @@ use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
-use crate::division::state::BlockDivisionState;
+use crate::division::{
+    analysis::FairnessReport, basis::BasisError, history::EventRecord, ledger::LedgerEntry,
+    lottery::RankVerification, selections::Selection, state::BlockDivisionState,
+    waitlist::Promotion,
+};
 
 pub trait BlockDivisionServerResponse: Serialize {}
 
@@ -11,8 +15,21 @@ pub struct SingleBlockDivisionState {
     pub user_id: i32,
     pub state_id: String,
     pub state: BlockDivisionState,
+    #[serde(default)]
+    pub max_buckets: Option<u32>, //The participant's cap on bucket weight, None for no limit
+    #[serde(default)]
+    pub quota_used: u32,
 }
 
 impl BlockDivisionServerResponse for SingleBlockDivisionState {}
 impl BlockDivisionServerResponse for bool {}
 impl BlockDivisionServerResponse for BTreeMap<String, BlockDivisionState> {}
+impl BlockDivisionServerResponse for RankVerification {}
+impl BlockDivisionServerResponse for String {}
+impl BlockDivisionServerResponse for BTreeMap<String, Vec<LedgerEntry>> {}
+impl BlockDivisionServerResponse for Vec<Promotion> {}
+impl BlockDivisionServerResponse for Vec<Option<Selection>> {}
+impl BlockDivisionServerResponse for FairnessReport {}
+impl BlockDivisionServerResponse for Vec<BasisError> {}
+impl BlockDivisionServerResponse for BlockDivisionState {}
+impl BlockDivisionServerResponse for Vec<EventRecord> {}
@@ -1,11 +1,19 @@
 import { type UserViewResult } from "../post/block_division_post";
+import type { Explanation, Rival } from "../post/results/block_division_state";
 
 export let get_participant_name = (view: UserViewResult, participant_index: number) => {
     return view.state.basis.participant_definitions[participant_index].name;
 };
 
 export let get_ancillary_name = (view: UserViewResult, bucket_index: number, ancillary_index: number) => {
-    return view.state.basis.bucket_definitions[bucket_index].available_ancillaries[ancillary_index];
+    let ancillary = view.state.basis.bucket_definitions[bucket_index].available_ancillaries[ancillary_index];
+    if (typeof ancillary === "string") {
+        return ancillary;
+    } else if ("shared" in ancillary) {
+        return view.state.basis.ancillary_definitions?.[ancillary.shared]?.name ?? "";
+    } else {
+        return ancillary.name;
+    }
 }
 
 export let get_designations = (view: UserViewResult, round_index: number, bucket_index: number) => {
@@ -20,15 +28,42 @@ export let get_designations = (view: UserViewResult, round_index: number, bucket
     return retval;
 };
 
+//Every round and bucket the participant is designated to
+export let get_holdings = (view: UserViewResult, participant_index: number) => {
+    let retval: { round_index: number, bucket_index: number }[] = [];
+    view.state.basis.selection_round_names.forEach((_round_name, round_index) => {
+        view.state.basis.bucket_definitions.forEach((_bucket, bucket_index) => {
+            if (view.state.bucket_states[bucket_index].round_states[round_index].designations.includes(participant_index)) {
+                retval.push({ round_index: round_index, bucket_index: bucket_index });
+            }
+        });
+    });
+    return retval;
+};
+
+//Holdings of everyone else in finalized rounds, which are the ones that can be swapped
+export let get_swappable_holdings = (view: UserViewResult, participant_index: number) => {
+    let retval: { participant: number, round_index: number, bucket_index: number }[] = [];
+    view.state.basis.participant_definitions.forEach((_participant, other) => {
+        if (other !== participant_index) {
+            for (const holding of get_holdings(view, other)) {
+                if (view.state.finalized_rounds.includes(holding.round_index)) {
+                    retval.push({ participant: other, ...holding });
+                }
+            }
+        }
+    });
+    return retval;
+};
+
 export let get_ancillary_designations = (view: UserViewResult, round_index: number, bucket_index: number) => {
     let designations = view.state.bucket_states[bucket_index].round_states[round_index].ancillary_designations;
 
     let retval: string[] = [];
     for (let ancillary_index in designations) {
         let ancillary_name = get_ancillary_name(view, bucket_index, parseInt(ancillary_index));
-        let participant_index = designations[ancillary_index];
-        let participant_name: string = get_participant_name(view, participant_index);
-        retval.push(ancillary_name + ": " + participant_name);
+        let participant_names = designations[ancillary_index].map((participant_index) => get_participant_name(view, participant_index));
+        retval.push(ancillary_name + ": " + participant_names.join(", "));
     }
 
     return retval;
@@ -57,4 +92,55 @@ export let get_sorted_rankings = (view: UserViewResult, round_index: number, buc
     }
 
     return retval;
-};
\ No newline at end of file
+};
+
+let describe_rivals = (view: UserViewResult, rivals: Rival[]) => {
+    return rivals
+        .map((rival) => get_participant_name(view, rival.participant) + (rival.rank === null ? "" : " (rank " + rival.rank + ")"))
+        .join(", ");
+};
+
+//One sentence on why an option of a pick ended as it did
+export let describe_explanation = (view: UserViewResult, bucket_index: number, explanation: Explanation) => {
+    let bucket_name = view.state.basis.bucket_definitions[bucket_index].name;
+    if (explanation === "AlreadyDesignated") {
+        return "An earlier pick already holds " + bucket_name + ".";
+    } else if (explanation === "BucketRemoved") {
+        return bucket_name + " was removed from the division.";
+    } else if ("Granted" in explanation) {
+        return "Granted " + bucket_name + (explanation.Granted.rank === null ? "." : " at rank " + explanation.Granted.rank + ".");
+    } else if ("Outranked" in explanation) {
+        let rank = explanation.Outranked.rank === null ? "" : " Your rank was " + explanation.Outranked.rank + ".";
+        return bucket_name + " went to " + describe_rivals(view, explanation.Outranked.holders) + "." + rank;
+    } else if ("SlotsUsed" in explanation) {
+        let used = explanation.SlotsUsed;
+        let retval = bucket_name + " has " + used.slots + " slots. " + used.earlier_rounds + " went in earlier rounds.";
+        if (used.holders.length > 0) {
+            retval += " Earlier picks this round took the rest: " + describe_rivals(view, used.holders) + ".";
+        }
+        return retval;
+    } else if ("AncillaryUnavailable" in explanation) {
+        return explanation.AncillaryUnavailable.map((shortfall) => {
+            let name = get_ancillary_name(view, bucket_index, shortfall.ancillary);
+            if (!shortfall.eligible) {
+                return "You aren't eligible for " + name + ".";
+            }
+            let holders = shortfall.holders.map((holder) => {
+                let where = view.state.basis.bucket_definitions[holder.bucket_index].name + ", " + view.state.basis.selection_round_names[holder.round];
+                return get_participant_name(view, holder.participant) + " (" + where + (holder.rank === null ? "" : ", rank " + holder.rank) + ")";
+            });
+            return name + " holds " + shortfall.capacity + ". Held by " + holders.join(", ") + ".";
+        }).join(" ");
+    } else if ("CalendarConflict" in explanation) {
+        return "Overlaps " + explanation.CalendarConflict.map((conflict) => view.state.basis.bucket_definitions[conflict].name).join(", ") + ", which you already hold.";
+    } else if ("QuotaReached" in explanation) {
+        let quota = explanation.QuotaReached;
+        return bucket_name + " counts " + quota.requested + " toward your limit of " + quota.max_buckets + ", and you already hold " + quota.used + ".";
+    } else if ("Released" in explanation) {
+        return "Removed from " + bucket_name + " by " + (explanation.Released.by === "Admin" ? "an administrator" : "you") + ".";
+    } else if ("SwappedAway" in explanation) {
+        return "Traded " + bucket_name + " away in a swap.";
+    } else {
+        return "Given up for a choice you ranked higher when a place opened up.";
+    }
+};
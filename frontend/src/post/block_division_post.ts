@@ -1,11 +1,22 @@
+import type { AmendBasis } from "./posts/amend_basis";
+import type { Analyze } from "./posts/analyze";
+import type { CloseRound } from "./posts/close_round";
+import type { GetCalendar } from "./posts/get_calendar";
+import type { GetLedger, RecordLedger } from "./posts/ledger";
 import type { DeleteState } from "./posts/delete_state";
+import type { GetHistory, GetStateAt } from "./posts/history";
 import type { GetStates } from "./posts/get_states";
 import type { GetUserView, GetUserViewAsAdmin } from "./posts/get_user_view";
 import type { NewBasis } from "./posts/new_basis";
+import type { OverrideDesignation, RemoveOverride } from "./posts/override";
+import type { RemoveDesignation } from "./posts/remove_designation";
+import type { SetWithdrawalPolicy, Withdraw } from "./posts/withdraw";
 import type { SendStartEmail } from "./posts/send_start_email";
 import type { SetOpenRound } from "./posts/set_open_round";
 import type { SubmitSelections } from "./posts/submit_selections";
-import type { BlockDivisionState, BlockDivisionStateList } from "./results/block_division_state";
+import type { ProposeSwap, RespondToSwap } from "./posts/swap";
+import type { VerifyRanks } from "./posts/verify_ranks";
+import type { BlockDivisionSelectionEntry, BlockDivisionState, BlockDivisionStateList, EventRecord, LotteryAlgorithm } from "./results/block_division_state";
 
 export type BlockDivisionPost =
     { GetUserView: GetUserView } |
@@ -15,16 +26,89 @@ export type BlockDivisionPost =
     { NewBasis: NewBasis } |
     { DeleteState: DeleteState } |
     { SendStartEmail: SendStartEmail } |
-    { GetUserViewAsAdmin: GetUserViewAsAdmin };
+    { GetUserViewAsAdmin: GetUserViewAsAdmin } |
+    { VerifyRanks: VerifyRanks } |
+    { CloseRound: CloseRound } |
+    { GetCalendar: GetCalendar } |
+    { GetLedger: GetLedger } |
+    { RecordLedger: RecordLedger } |
+    { RemoveDesignation: RemoveDesignation } |
+    { Withdraw: Withdraw } |
+    { SetWithdrawalPolicy: SetWithdrawalPolicy } |
+    { ProposeSwap: ProposeSwap } |
+    { RespondToSwap: RespondToSwap } |
+    { SimulateSelections: SubmitSelections } |
+    { Analyze: Analyze } |
+    { AmendBasis: AmendBasis } |
+    { OverrideDesignation: OverrideDesignation } |
+    { RemoveOverride: RemoveOverride } |
+    { GetStateAt: GetStateAt } |
+    { GetHistory: GetHistory };
 
 export type ErrorResult = { error: Error };
-export type UserViewResult = { user_id?: number, state_id: string, state: BlockDivisionState };
+export type UserViewResult = {
+    user_id?: number,
+    state_id: string,
+    state: BlockDivisionState,
+    max_buckets?: number | null,
+    quota_used?: number
+};
+export type RankVerificationResult = {
+    algorithm: LotteryAlgorithm,
+    seed: string,
+    weights: number[] | null,
+    matches: boolean,
+    mismatches: { bucket_index: number, round_index: number }[]
+};
+export type LedgerEntry = {
+    division_id: string,
+    email: string,
+    name: string,
+    wins: number,
+    losses: number,
+    holiday_buckets: number
+};
+export type LedgerResult = { [email: string]: LedgerEntry[] };
+//Projected picks for a SimulateSelections post. Nothing is saved.
+export type SimulationResult = BlockDivisionSelectionEntry[];
+export type PromotionResult = { round: number, bucket_index: number, participant: number, notified: boolean }[];
+export type FairnessReport = {
+    runs: number,
+    seed: number,
+    participants: {
+        name: string,
+        expected_first_choices: number,
+        expected_buckets: number,
+        bucket_probabilities: number[]
+    }[]
+};
+//Every problem found with a NewBasis, which isn't saved
+export type BasisErrors = { field: string, message: string }[];
+export type HistoryResult = EventRecord[];
 export type BlockDivisionPostResult =
     ErrorResult |
     BlockDivisionStateList |
     UserViewResult |
+    RankVerificationResult |
+    LedgerResult |
+    PromotionResult |
+    SimulationResult |
+    FairnessReport |
+    BasisErrors |
+    BlockDivisionState |
+    HistoryResult |
+    string |
     boolean;
 
+//Participant .ics feed. Pass the hash from the participant's link, and whole_division for everyone's designations.
+export let block_division_calendar_url = (hash: string, whole_division: boolean) => {
+    let url = import.meta.env.VITE_POST_ROOT + "block_division_calendar?hash=" + encodeURIComponent(hash);
+    if (whole_division) {
+        url += "&scope=division";
+    }
+    return url;
+};
+
 export let block_division_post = (post: BlockDivisionPost, callback: (result: BlockDivisionPostResult) => void) => {
     //fetch("http://localhost:8181/block_division_post", {
     fetch(import.meta.env.VITE_POST_ROOT + "block_division_post", {
@@ -0,0 +1,6 @@
+import type { BasisAmendment } from "../results/block_division_state";
+
+export interface AmendBasis {
+    id: string,
+    amendment: BasisAmendment
+}
@@ -0,0 +1,14 @@
+import type { BlockDivisionSelectionEntry } from "../results/block_division_state";
+import type { Basis } from "../results/state_components/basis";
+
+//Preferences by round, then participant index
+export type Preferences = { [round: number]: { [participant: number]: BlockDivisionSelectionEntry[] } };
+
+//Give either a stored division's id or an unpublished basis. A basis needs preferences.
+export interface Analyze {
+    id: string | null,
+    basis: Basis | null,
+    preferences: Preferences | null, //null to use the submitted selections
+    runs: number,
+    seed: number | null //null for a random seed
+}
@@ -0,0 +1,4 @@
+export interface CloseRound {
+    id: string,
+    round: number
+}
@@ -0,0 +1,4 @@
+export interface GetCalendar {
+    id: string,
+    user_id: number | null //null for the whole division
+}
@@ -0,0 +1,10 @@
+//The division as it was at the given time, rebuilt from its recorded events
+export interface GetStateAt {
+    id: string,
+    at: string //RFC 3339
+}
+
+//Every recorded event of the division, oldest first
+export interface GetHistory {
+    id: string
+}
@@ -0,0 +1,8 @@
+export interface GetLedger {
+    email: string | null //null for everyone
+}
+
+//Records a completed division, for divisions completed before the ledger existed
+export interface RecordLedger {
+    id: string
+}
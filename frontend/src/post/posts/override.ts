@@ -0,0 +1,16 @@
+//Places a participant in a bucket regardless of rank, slots or quota
+export interface OverrideDesignation {
+    id: string,
+    round: number,
+    bucket_index: number,
+    user_id: number,
+    ancillaries?: number[]
+}
+
+//Promotes the next eligible waitlisted participant if the round is finalized
+export interface RemoveOverride {
+    id: string,
+    round: number,
+    bucket_index: number,
+    user_id: number
+}
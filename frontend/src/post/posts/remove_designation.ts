@@ -0,0 +1,7 @@
+//Promotes the next eligible waitlisted participant and e-mails them
+export interface RemoveDesignation {
+    id: string,
+    round: number,
+    bucket_index: number,
+    user_id: number
+}
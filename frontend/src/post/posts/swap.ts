@@ -0,0 +1,16 @@
+import type { Holding } from "../results/block_division_state";
+
+//Offer the participant's holding for the recipient's
+export interface ProposeSwap {
+    hash: string, //The proposer's link, which identifies them and the division
+    give: Holding,
+    recipient: number,
+    take: Holding
+}
+
+//The recipient accepts or declines. The proposer can only decline, which cancels it.
+export interface RespondToSwap {
+    hash: string,
+    swap_index: number,
+    accept: boolean
+}
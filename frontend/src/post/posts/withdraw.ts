@@ -0,0 +1,13 @@
+import type { WithdrawalPolicy } from "../results/block_division_state";
+
+//A participant giving up their own designation
+export interface Withdraw {
+    hash: string, //The participant's link, which identifies them and the division
+    round: number,
+    bucket_index: number
+}
+
+export interface SetWithdrawalPolicy {
+    id: string,
+    policy: WithdrawalPolicy
+}
@@ -1,13 +1,48 @@
-import type { BucketIndex, AncillaryIndex, Basis, RoundIndex, ParticipantIndex } from "./state_components/basis";
+import type { BucketIndex, AncillaryIndex, Basis, BucketDefinition, ParticipantDefinition, RoundIndex, ParticipantIndex, RoundSchedule } from "./state_components/basis";
 import type { BucketState } from "./state_components/state";
+import type { Release } from "./state_components/round";
 
 export type BlockDivisionStateList = { [label: string]: BlockDivisionState }
 
 
+export type BlockDivisionSelectionResult = "Confirmed" | "RejectedOutranked" | "RejectedNoSelectionsThisRound" | "RejectedAlreadyDesignated" | "RejectedQuotaReached" | "RejectedBucketRemoved" | "Released" | "SwappedAway" | { RejectedAncillaryUnavailable: number[] } | { RejectedCalendarConflict: number[] } | null;
+
+export interface BlockDivisionSelectionOption {
+    bucket_index: BucketIndex;
+    ancillaries: AncillaryIndex[];
+    state: BlockDivisionSelectionResult
+}
+
+//Ranks are null in rounds whose ranks are hidden
+export type Rival = { participant: ParticipantIndex, rank: number | null };
+export type AncillaryShortfall = {
+    ancillary: AncillaryIndex,
+    capacity: number,
+    eligible: boolean,
+    holders: { participant: ParticipantIndex, bucket_index: BucketIndex, round: RoundIndex, rank: number | null }[]
+};
+
+//Why an option of a pick ended as it did
+export type Explanation =
+    { Granted: { rank: number | null } } |
+    { Outranked: { rank: number | null, holders: Rival[] } } |
+    { SlotsUsed: { slots: number, earlier_rounds: number, holders: Rival[] } } |
+    { AncillaryUnavailable: AncillaryShortfall[] } |
+    "AlreadyDesignated" |
+    { CalendarConflict: BucketIndex[] } |
+    { QuotaReached: { used: number, requested: number, max_buckets: number } } |
+    { Released: { by: "Admin" | "Participant", at: string } } |
+    { GivenUp: { option_index: number } } |
+    "BucketRemoved" |
+    { SwappedAway: { swap_index: number } };
+
 export interface BlockDivisionSelection {
     bucket_index: BucketIndex;
     ancillaries: AncillaryIndex[];
-    state: "Confirmed" | "RejectedOutranked" | "RejectedNoSelectionsThisRound" | { RejectedAncillaryUnavailable: number[] } | null
+    fallbacks: BlockDivisionSelectionOption[];
+    state: BlockDivisionSelectionResult;
+    granted_option: number | null,
+    explanations?: { [option_index: number]: Explanation } //0 is the first choice, n is fallbacks[n-1]
 }
 
 export type BlockDivisionSelectionEntry = BlockDivisionSelection | null;
@@ -18,19 +53,117 @@ export function clone_block_division_selection(original: BlockDivisionSelection
     let bucket_index = original.bucket_index;
     let state = original.state;
     let ancillaries: AncillaryIndex[] = [...original.ancillaries];
+    let fallbacks: BlockDivisionSelectionOption[] = (original.fallbacks ?? []).map((fallback) => {
+        return {
+            bucket_index: fallback.bucket_index,
+            ancillaries: [...fallback.ancillaries],
+            state: fallback.state
+        };
+    });
 
     let retval: BlockDivisionSelection = {
         bucket_index: bucket_index,
         ancillaries: ancillaries,
-        state: state
+        fallbacks: fallbacks,
+        state: state,
+        granted_option: original.granted_option ?? null
     };
 
     return retval;
 }
 
+//Who may withdraw a participant from a finalized round. Anyone may while the round is open.
+export type WithdrawalPolicy = "Frozen" | "AdminOnly" | "Anyone";
+
+export interface Holding {
+    round: RoundIndex,
+    bucket_index: BucketIndex
+}
+
+export type SwapStatus = "Proposed" | "Accepted" | "Declined" | "Cancelled";
+
+//The proposer gives up their holding for the recipient's
+export interface SwapProposal {
+    proposer: ParticipantIndex,
+    proposer_holding: Holding,
+    recipient: ParticipantIndex,
+    recipient_holding: Holding,
+    status: SwapStatus,
+    proposed_at: string,
+    resolved_at: string | null
+}
+
+export type LotteryAlgorithm = "ChaCha20V1";
+
+export interface LotteryRecord {
+    algorithm: LotteryAlgorithm,
+    seed: string | null,
+    weights?: number[] | null //Participant weights used for the draw, null if uniform
+}
+
+//Removed participants and buckets stay in the basis, marked removed, so indices don't shift
+export type BasisAmendment =
+    { AddParticipant: ParticipantDefinition } |
+    { RemoveParticipant: ParticipantIndex } |
+    { UpdateParticipant: { participant: ParticipantIndex, name: string, email: string } } |
+    { SetPicks: { participant: ParticipantIndex, round: RoundIndex, picks: number } } |
+    { AddBucket: BucketDefinition } |
+    { RemoveBucket: BucketIndex } |
+    { SetSlots: { bucket_index: BucketIndex, slots: number } } |
+    { SetSchedule: { round: RoundIndex, schedule: RoundSchedule } };
+
+export interface Amendment {
+    change: BasisAmendment,
+    at: string,
+    lottery: LotteryRecord | null //Draws the ranks of an added participant or bucket
+}
+
+export interface ScheduledChange {
+    round: RoundIndex,
+    event: "Open" | "Close"
+}
+
+export interface OverrideRecord {
+    round: RoundIndex,
+    bucket_index: BucketIndex,
+    participant: ParticipantIndex,
+    ancillaries: AncillaryIndex[],
+    assigned: boolean, //false when the override was removed
+    at: string
+}
+
 export interface BlockDivisionState {
     basis: Basis,
     bucket_states: { [bucket_index: BucketIndex]: BucketState },
     current_open_round: RoundIndex | null,
+    lottery: LotteryRecord | null,
+    finalized_rounds: RoundIndex[],
+    withdrawal_policy?: WithdrawalPolicy,
+    swaps?: SwapProposal[],
+    amendments?: Amendment[],
+    override_log?: OverrideRecord[],
+    schedule_applied?: ScheduledChange[], //Scheduled opens and closes already carried out
     selections: { state: { [round_index: RoundIndex]: { [participant_index: ParticipantIndex]: BlockDivisionSelectionEntry[] } } }
+}
+
+//One recorded change. Replaying every event of a division gives its current state.
+export type DivisionEvent =
+    { Created: { basis: Basis, lottery: LotteryRecord } } |
+    { Imported: BlockDivisionState } | //Divisions from before events were recorded, as of their first recorded change
+    { SelectionsSubmitted: { participant: ParticipantIndex, selections: BlockDivisionSelectionEntry[] } } |
+    { RoundOpened: RoundIndex | null } |
+    { RoundClosed: RoundIndex } |
+    { ScheduleApplied: ScheduledChange } |
+    { Withdrawn: { round: RoundIndex, bucket_index: BucketIndex, participant: ParticipantIndex, release: Release } } |
+    { SwapProposed: { proposer: ParticipantIndex, proposer_holding: Holding, recipient: ParticipantIndex, recipient_holding: Holding, at: string } } |
+    { SwapResolved: { participant: ParticipantIndex, swap_index: number, accept: boolean, at: string } } |
+    { WithdrawalPolicySet: WithdrawalPolicy } |
+    { OverrideApplied: { round: RoundIndex, bucket_index: BucketIndex, participant: ParticipantIndex, ancillaries: AncillaryIndex[], at: string } } |
+    { OverrideRemoved: { round: RoundIndex, bucket_index: BucketIndex, participant: ParticipantIndex, at: string } } |
+    { Amended: Amendment } |
+    "Deleted";
+
+export interface EventRecord {
+    at: string,
+    event: DivisionEvent
 }
\ No newline at end of file
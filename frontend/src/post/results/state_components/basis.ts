@@ -7,16 +7,48 @@ export interface Basis {
     bucket_definitions: BucketDefinition[],
     participant_definitions: ParticipantDefinition[],
     selection_round_names: string[],
+    ancillary_definitions?: AncillaryDefinition[], //Shared between every bucket that offers { shared: index }
+    allocation?: Allocation, //Defaults to PerBucketLottery
+    weighted_lottery?: boolean, //Draw ranks using participant weights
+    ledger_weighted?: boolean, //Draw ranks weighted by losses and missed holidays in past divisions
+    round_schedules?: RoundSchedule[], //One per selection round, or none
 }
 
+//RFC 3339 times. Closing finalizes the round. null or missing if left to the admin.
+export interface RoundSchedule {
+    open?: string | null,
+    close?: string | null,
+    reminder_hours?: number[] //Hours before close to e-mail anyone who hasn't submitted. Needs a close time.
+}
+
+//PerBucketLottery draws separate ranks for every bucket. SerialDictatorship draws one order per round for all buckets.
+export type Allocation = "PerBucketLottery" | "SerialDictatorship";
+
+export interface AncillaryDefinition {
+    name: string,
+    capacity?: number, //Defaults to 1
+    eligible_participants?: ParticipantIndex[] | null //null or missing if anyone may request it
+}
+
+//A plain name holds one participant, like a definition with the default capacity
+export type BucketAncillary = string | AncillaryDefinition | { shared: number };
+
 export interface BucketDefinition {
     name: string,
     available_slots: number,
-    available_ancillaries: string[],
+    available_ancillaries: BucketAncillary[],
+    start: string | null, //YYYY-MM-DD, inclusive
+    end: string | null,
+    holiday?: boolean, //Counted in the fairness ledger
+    quota_weight?: number, //How much of a participant's max_buckets this bucket uses. Defaults to 1.
+    removed?: boolean, //Taken out by an amendment after the division started
 }
 
 export interface ParticipantDefinition {
     name: string,
     email: string,
-    round_picks_allowed: number[]
+    round_picks_allowed: number[],
+    weight?: number, //Seniority weight, at least 1. Defaults to 1.
+    max_buckets?: number | null, //Most bucket weight confirmed over the whole division. null or missing for no limit.
+    removed?: boolean //Taken out by an amendment after the division started
 }
@@ -1,7 +1,21 @@
 import type { AncillaryIndex, ParticipantIndex } from "./basis"
 
+export interface WaitlistEntry {
+    participant: ParticipantIndex,
+    pick_index: number,
+    option_index: number //0 is the first choice, n is fallbacks[n-1]
+}
+
+export interface Release {
+    by: "Admin" | "Participant",
+    at: string
+}
+
 export interface RoundState {
-    ancillary_designations: { [ancillary_index: AncillaryIndex]: ParticipantIndex }
+    ancillary_designations: { [ancillary_index: AncillaryIndex]: ParticipantIndex[] }
     designations: [ParticipantIndex]
     ranks: { [participant_index: ParticipantIndex]: number } | null
-}
\ No newline at end of file
+    waitlist?: WaitlistEntry[] //Best rank first. Empty where ranks are censored.
+    released?: { [participant_index: ParticipantIndex]: Release }
+    overrides?: { [participant_index: ParticipantIndex]: AncillaryIndex[] } //Placed by an admin outside the lottery
+}